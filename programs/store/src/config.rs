@@ -1,26 +1,26 @@
-use anchor_lang::prelude::*;
-
 pub const MAX_CAP: u64       = 1000_000_000_000_000; 
 pub const MIN_CAP: u64       = 100_000_000_000;
 pub const FIRST_INTEREST: u64       = 50_000_000;
 pub const SECOND_INTEREST: u64      = 50_000_000;
+pub const MAX_TOTAL_INTEREST: u64   = 200_000_000;
 
+pub const ADMIN_TAG: &[u8]          = b"ADMIN";
 pub const EPOC_TAG: &[u8]           = b"EPOC";
 pub const CUSTOMER_TAG: &[u8]       = b"CUSTOMER";
 pub const PROMOTER_TAG: &[u8]       = b"PROMOTER";
+pub const VAULT_TAG: &[u8]          = b"VAULT";
+pub const ALLOCATION_TAG: &[u8]     = b"ALLOCATION";
+pub const ESCROW_TAG: &[u8]         = b"ESCROW";
+pub const PAYMENT_TOKEN_TAG: &[u8]  = b"PAYMENT_TOKEN";
+pub const CONTRIBUTION_TAG: &[u8]   = b"CONTRIBUTION";
+pub const REFERENCE_PRICE_TAG: &[u8] = b"REFERENCE_PRICE";
+pub const REFERRAL_CODE_TAG: &[u8]  = b"REFERRAL_CODE";
 pub const EMPTY_PROMOTER: &str      = "9XwXqTuy86VKLLhzEU5ktSWT4efGPnFFWxUmFzUywsqy";
 pub const TREASURY: &str            = "2vrYa73jwsAvkdtPYVaeCbd9yGu9TvVXZgNwyP8nXUY6";
 
 pub const STALENESS_THRESHOLD: u64  = 60;
-pub const SOL_USD_PRICEFEED: &str   = "H6ARHf6YXhGYeQfUzQNGk6rDNnLBQKrenN712K4AQJEG";
-
-pub const PRECISION: u32            = 9;
-pub const STABLE_PRECISION: u32     = 3;
-pub const USDT: &str                = "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB";
-pub const USDC: &str                = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
-
-const ADMINS: &[&str] = &["D9eS7qChQzpuiF9M3jg3VebZFwSnbnEfDUgb4xcr4Suf", "3LByBjJxGECZtTVPH6WKH2rsbCYGeuKiJUp95NztncYN", "BRm2knMo3Dob5xY6Vh1rYSZfGgFf2ThUk5no5vJGtpoQ"];
+pub const MAX_CONFIDENCE_RATIO: u64 = 20_000_000;
+pub const BASIS_POINTS: u64         = 10_000;
+pub const SOL_USD_FEED_ID: &str     = "0xef0d8b6fda2ceba41da15d4095d1da392a0d2f8ed0c6c7bc0f4cfac8c280b56d";
 
-pub fn only_admin(address: Pubkey) -> bool {
-  return  ADMINS.contains(&address.to_string().as_str());
-}
+pub const PRECISION: u32            = 9;
\ No newline at end of file
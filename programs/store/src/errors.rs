@@ -42,4 +42,92 @@ pub enum Store {
   PriceIsDown,
   #[msg("Promoter no funds")]
   PromoterNoFunds,
+  #[msg("Admin already exists")]
+  AdminExists,
+  #[msg("Admin not found")]
+  AdminNotFound,
+  #[msg("Admin list is full")]
+  AdminListFull,
+  #[msg("Wrong pending owner")]
+  WrongPendingOwner,
+  #[msg("Store not disabled")]
+  StoreNotDisabled,
+  #[msg("Store soft cap not reached")]
+  StoreSoftCapNotReached,
+  #[msg("Store soft cap reached")]
+  StoreSoftCapReached,
+  #[msg("Store wrong TGE timestamp")]
+  StoreWrongTge,
+  #[msg("Store TGE already set")]
+  StoreTgeLocked,
+  #[msg("Store epoc queue is full")]
+  StoreEpocQueueFull,
+  #[msg("Store wrong epoc queue")]
+  StoreWrongEpocQueue,
+  #[msg("Store wrong confidence ratio")]
+  StoreWrongConfidenceRatio,
+  #[msg("Store wrong circuit breaker")]
+  StoreWrongCircuitBreaker,
+  #[msg("Store upline levels exceeded")]
+  StoreUplineLevelsExceeded,
+  #[msg("Epoc TGE unlock too large")]
+  EpocTgeUnlockTooLarge,
+  #[msg("Epoc wrong vesting schedule")]
+  EpocWrongVesting,
+  #[msg("Epoc wrong sale window")]
+  EpocWrongWindow,
+  #[msg("Epoc outside sale window")]
+  EpocOutsideWindow,
+  #[msg("Payment token not enabled")]
+  PaymentTokenNotEnabled,
+  #[msg("Payment token price not set")]
+  PaymentTokenWrongPrice,
+  #[msg("Payment token depegged")]
+  PaymentTokenDepegged,
+  #[msg("Wrong escrow account")]
+  WrongEscrow,
+  #[msg("Deposit slippage exceeded")]
+  SlippageExceeded,
+  #[msg("Price older than the requested max age")]
+  PriceTooOld,
+  #[msg("Oracle price feed is invalid")]
+  PriceFeedInvalid,
+  #[msg("Oracle price verification level too low")]
+  PriceVerificationTooLow,
+  #[msg("Reference price unavailable")]
+  PriceReferenceUnavailable,
+  #[msg("Oracle and reference prices diverge")]
+  PriceDeviationTooLarge,
+  #[msg("Price moved too far from the last accepted price")]
+  PriceMoveTooLarge,
+  #[msg("Oracle price is not positive")]
+  PriceNotPositive,
+  #[msg("Oracle price confidence too wide")]
+  PriceConfidenceTooWide,
+  #[msg("Oracle price wrong exponent")]
+  PriceWrongExponent,
+  #[msg("Promoter disabled")]
+  PromoterDisabled,
+  #[msg("Promoter not registered")]
+  PromoterNotRegistered,
+  #[msg("Promoter cannot refer themselves")]
+  SelfReferral,
+  #[msg("Wrong promoter upline account")]
+  PromoterWrongUpline,
+  #[msg("Referral code is invalid")]
+  ReferralCodeInvalid,
+  #[msg("Referral code revoked")]
+  ReferralCodeRevoked,
+  #[msg("Customer no funds")]
+  CustomerNoFunds,
+  #[msg("Customer allocation exhausted")]
+  CustomerAllocationExceeded,
+  #[msg("Customer not whitelisted")]
+  CustomerNotWhitelisted,
+  #[msg("Wrong sale token mint")]
+  WrongMint,
+  #[msg("Account already migrated")]
+  AccountMigrated,
+  #[msg("Math overflow")]
+  MathOverflow,
 }
\ No newline at end of file
@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use crate::state::admin::Role;
 
 #[event]
 pub struct DepositWithSolEvent {
@@ -10,37 +11,177 @@ pub struct DepositWithSolEvent {
 }
 
 #[event]
-pub struct DepositWithUsdtEvent {
+pub struct DepositWithTokenEvent {
   pub epoc: i16,
   pub customer: Pubkey,
   pub promoter: Pubkey,
-  pub usdt_amount: u64,
+  pub mint: Pubkey,
+  pub amount: u64,
   pub asset_amount: u128,
 }
 
 #[event]
-pub struct DepositWithUsdcEvent {
+pub struct WithdrawSolEvent {
+  pub promoter: Pubkey,
+  pub amount: u64,
+}
+
+#[event]
+pub struct WithdrawTokenEvent {
+  pub promoter: Pubkey,
+  pub mint: Pubkey,
+  pub amount: u64,
+}
+
+#[event]
+pub struct FundVaultEvent {
+  pub mint: Pubkey,
+  pub amount: u64,
+}
+
+#[event]
+pub struct ClaimEvent {
   pub epoc: i16,
   pub customer: Pubkey,
-  pub promoter: Pubkey,
-  pub usdc_amount: u64,
   pub asset_amount: u128,
+  pub amount: u64,
 }
 
 #[event]
-pub struct WithdrawSolEvent {
+pub struct ClaimBonusEvent {
+  pub epoc: i16,
   pub promoter: Pubkey,
+  pub asset_amount: u128,
   pub amount: u64,
 }
 
 #[event]
-pub struct WithdrawUsdtEvent {
-  pub promoter: Pubkey,
+pub struct ReleaseEvent {
+  pub sol_amount: u64,
+}
+
+#[event]
+pub struct ReleaseTokenEvent {
+  pub mint: Pubkey,
   pub amount: u64,
 }
 
 #[event]
-pub struct WithdrawUsdcEvent {
+pub struct RefundEvent {
+  pub customer: Pubkey,
+  pub sol_amount: u64,
+}
+
+#[event]
+pub struct RefundTokenEvent {
+  pub customer: Pubkey,
+  pub mint: Pubkey,
+  pub amount: u64,
+}
+
+#[event]
+pub struct AddAdminEvent {
+  pub role: Role,
+  pub admin: Pubkey,
+}
+
+#[event]
+pub struct RemoveAdminEvent {
+  pub role: Role,
+  pub admin: Pubkey,
+}
+
+#[event]
+pub struct TransferOwnershipEvent {
+  pub owner: Pubkey,
+  pub pending_owner: Pubkey,
+}
+
+#[event]
+pub struct AcceptOwnershipEvent {
+  pub previous_owner: Pubkey,
+  pub owner: Pubkey,
+}
+
+#[event]
+pub struct SetTreasuryEvent {
+  pub treasury: Pubkey,
+}
+
+#[event]
+pub struct SetPriceFeedEvent {
+  pub feed_id: [u8; 32],
+}
+
+
+#[event]
+pub struct SetPaymentTokenPriceEvent {
+  pub mint: Pubkey,
+  pub feed_id: [u8; 32],
+  pub price: u64,
+  pub peg_tolerance_bps: u64,
+}
+
+#[event]
+pub struct EpocRolloverEvent {
+  pub previous_epoc: i16,
+  pub epoc: i16,
+}
+
+#[event]
+pub struct SetReferencePriceEvent {
+  pub price: u64,
+  pub updated_ts: i64,
+}
+
+#[event]
+pub struct SetCircuitBreakerEvent {
+  pub max_deviation_bps: u64,
+  pub max_price_move_bps: u64,
+  pub price_move_window: u64,
+  pub reference_max_age: u64,
+}
+
+#[event]
+pub struct PromoterIgnoredEvent {
+  pub customer: Pubkey,
+  pub promoter: Pubkey,
+  pub self_referral: bool,
+}
+
+#[event]
+pub struct SetPromoterPolicyEvent {
+  pub reject_invalid_promoter: bool,
+}
+
+#[event]
+pub struct SetReferrerEvent {
+  pub customer: Pubkey,
+  pub referrer: Pubkey,
+}
+
+#[event]
+pub struct UplineCommissionEvent {
+  pub customer: Pubkey,
   pub promoter: Pubkey,
+  pub level: u8,
   pub amount: u64,
 }
+
+#[event]
+pub struct RegisterPromoterEvent {
+  pub promoter: Pubkey,
+  pub code: String,
+}
+
+#[event]
+pub struct RevokeReferralCodeEvent {
+  pub promoter: Pubkey,
+  pub code: String,
+}
+
+#[event]
+pub struct MigrateEvent {
+  pub account: Pubkey,
+  pub size: u64,
+}
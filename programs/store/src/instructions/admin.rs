@@ -0,0 +1,154 @@
+use anchor_lang::prelude::*;
+use crate::config::ADMIN_TAG;
+
+use crate::events;
+use crate::errors;
+use crate::state::admin::*;
+
+pub fn init_admin(
+  ctx: Context<InitAdmin>,
+) -> Result<()> {
+  let admin = &mut ctx.accounts.admin;
+  admin.init(ctx.accounts.payer.key())
+}
+
+pub fn add_admin(
+  ctx: Context<AddAdmin>,
+  role: Role,
+  key: Pubkey,
+) -> Result<()> {
+  let admin = &mut ctx.accounts.admin;
+  admin.add(role.clone(), key)?;
+
+  emit!(events::AddAdminEvent {
+    role: role,
+    admin: key,
+  });
+
+  Ok(())
+}
+
+pub fn remove_admin(
+  ctx: Context<RemoveAdmin>,
+  role: Role,
+  key: Pubkey,
+) -> Result<()> {
+  let admin = &mut ctx.accounts.admin;
+  admin.remove(role.clone(), key)?;
+
+  emit!(events::RemoveAdminEvent {
+    role: role,
+    admin: key,
+  });
+
+  Ok(())
+}
+
+pub fn transfer_ownership(
+  ctx: Context<TransferOwnership>,
+  pending_owner: Pubkey,
+) -> Result<()> {
+  let admin = &mut ctx.accounts.admin;
+  admin.set_pending_owner(pending_owner)?;
+
+  emit!(events::TransferOwnershipEvent {
+    owner: admin.get_owner(),
+    pending_owner: pending_owner,
+  });
+
+  Ok(())
+}
+
+pub fn accept_ownership(
+  ctx: Context<AcceptOwnership>,
+) -> Result<()> {
+  let payer = &ctx.accounts.payer;
+  let admin = &mut ctx.accounts.admin;
+
+  let previous_owner = admin.get_owner();
+  admin.accept_owner(payer.key())?;
+
+  emit!(events::AcceptOwnershipEvent {
+    previous_owner: previous_owner,
+    owner: payer.key(),
+  });
+
+  Ok(())
+}
+
+#[derive(Accounts)]
+pub struct InitAdmin<'info> {
+  #[account(
+    init,
+    payer = payer,
+    space = 8 + Admin::MAX_SIZE,
+    seeds = [ADMIN_TAG],
+    bump,
+  )]
+  pub admin: Account<'info, Admin>,
+  #[account(
+    constraint = program.programdata_address()? == Some(program_data.key()),
+  )]
+  pub program: Program<'info, crate::program::Store>,
+  #[account(
+    constraint = program_data.upgrade_authority_address == Some(payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub program_data: Account<'info, ProgramData>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+  pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(role: Role, key: Pubkey)]
+pub struct AddAdmin<'info> {
+  #[account(
+    mut,
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.is_owner(payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(role: Role, key: Pubkey)]
+pub struct RemoveAdmin<'info> {
+  #[account(
+    mut,
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.is_owner(payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(pending_owner: Pubkey)]
+pub struct TransferOwnership<'info> {
+  #[account(
+    mut,
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.is_owner(payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptOwnership<'info> {
+  #[account(
+    mut,
+    seeds = [ADMIN_TAG],
+    bump,
+  )]
+  pub admin: Account<'info, Admin>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+}
\ No newline at end of file
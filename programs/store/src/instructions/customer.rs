@@ -0,0 +1,304 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{ Mint, Token, TokenAccount };
+use anchor_spl::token_interface::{ self, TokenInterface };
+use crate::config::{
+  ADMIN_TAG, CUSTOMER_TAG, ALLOCATION_TAG, VAULT_TAG, PAYMENT_TOKEN_TAG,
+  CONTRIBUTION_TAG, PROMOTER_TAG
+};
+
+use crate::events;
+use crate::errors;
+use crate::instructions::store::{ transfer_from_escrow, transfer_from_vault };
+use crate::state::admin::{ Admin, Role };
+use crate::state::store::Store;
+use crate::state::epoc::Epoc;
+use crate::state::allocation::Allocation;
+use crate::state::contribution::Contribution;
+use crate::state::payment_token::PaymentToken;
+use crate::state::customer::*;
+use crate::state::promoter::Promoter;
+
+pub fn claim(
+  ctx: Context<Claim>,
+) -> Result<()> {
+  let payer = &ctx.accounts.payer;
+  let store = &ctx.accounts.store;
+  let epoc = &ctx.accounts.epoc;
+  let customer = &mut ctx.accounts.customer;
+  let allocation = &mut ctx.accounts.allocation;
+
+  let mint = &ctx.accounts.mint;
+  let vault = &ctx.accounts.vault;
+  let customer_ata = &ctx.accounts.customer_ata;
+  let program = &ctx.accounts.asset_program;
+
+  if !store.is_soft_cap_reached() {
+    return err!(errors::Store::StoreSoftCapNotReached);
+  }
+
+  let current_timestamp = Clock::get()?.unix_timestamp;
+  let (asset_amount, amount) = allocation.release(epoc, store.get_tge(), current_timestamp, mint.decimals)?;
+  if amount == 0 {
+    return err!(errors::Store::CustomerNoFunds);
+  }
+
+  customer.set_claimed_amount(asset_amount)?;
+  transfer_from_vault(store, vault, customer_ata, program, ctx.bumps.store, amount)?;
+
+  emit!(events::ClaimEvent {
+    epoc: epoc.get_id(),
+    customer: payer.key(),
+    asset_amount: asset_amount,
+    amount: amount,
+  });
+
+  Ok(())
+}
+
+pub fn refund(
+  ctx: Context<Refund>,
+) -> Result<()> {
+  let payer = &mut ctx.accounts.payer;
+  let store = &mut ctx.accounts.store;
+  let customer = &mut ctx.accounts.customer;
+
+  if !store.is_disabled() {
+    return err!(errors::Store::StoreNotDisabled);
+  }
+
+  if store.is_soft_cap_reached() {
+    return err!(errors::Store::StoreSoftCapReached);
+  }
+
+  let sol_amount = customer.get_sol_amount();
+  if sol_amount == 0 {
+    return err!(errors::Store::CustomerNoFunds);
+  }
+
+  customer.reset_sol_amount()?;
+
+  store.sub_lamports(sol_amount)?;
+  payer.add_lamports(sol_amount)?;
+
+  emit!(events::RefundEvent {
+    customer: payer.key(),
+    sol_amount: sol_amount,
+  });
+
+  Ok(())
+}
+
+pub fn refund_token(
+  ctx: Context<RefundToken>,
+) -> Result<()> {
+  let payer = &ctx.accounts.payer;
+  let store = &ctx.accounts.store;
+  let payment_token = &ctx.accounts.payment_token;
+  let contribution = &mut ctx.accounts.contribution;
+
+  let mint = &ctx.accounts.mint;
+  let customer_ata = &ctx.accounts.customer_ata;
+  let escrow_ata = &ctx.accounts.escrow_ata;
+  let asset_program = &ctx.accounts.asset_program;
+
+  if !store.is_disabled() {
+    return err!(errors::Store::StoreNotDisabled);
+  }
+
+  if store.is_soft_cap_reached() {
+    return err!(errors::Store::StoreSoftCapReached);
+  }
+
+  let amount = contribution.get_amount();
+  if amount == 0 {
+    return err!(errors::Store::CustomerNoFunds);
+  }
+
+  contribution.reset_amount()?;
+  transfer_from_escrow(store, escrow_ata, customer_ata, mint, asset_program, ctx.bumps.store, amount)?;
+
+  emit!(events::RefundTokenEvent {
+    customer: payer.key(),
+    mint: payment_token.get_mint(),
+    amount: amount,
+  });
+
+  Ok(())
+}
+
+pub fn set_customer_referrer(
+  ctx: Context<SetCustomerReferrer>,
+  customer_key: Pubkey,
+  referrer: Pubkey,
+) -> Result<()> {
+  let customer = &mut ctx.accounts.customer;
+
+  if referrer == customer_key {
+    return err!(errors::Store::SelfReferral);
+  }
+
+  // The referrer must be a registered promoter, an empty key clears the binding
+  let registered = ctx.accounts.referrer_promoter.as_ref().is_some_and(|referrer_promoter| referrer_promoter.is_initialized());
+  if referrer != Pubkey::default() && !registered {
+    return err!(errors::Store::PromoterNotRegistered);
+  }
+
+  customer.set_referrer(referrer)?;
+
+  emit!(events::SetReferrerEvent {
+    customer: customer_key,
+    referrer: referrer,
+  });
+
+  Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(customer_key: Pubkey, referrer: Pubkey)]
+pub struct SetCustomerReferrer<'info> {
+  #[account(
+    mut,
+    seeds = [
+      CUSTOMER_TAG,
+      b"_",
+      customer_key.as_ref()
+    ],
+    bump
+  )]
+  pub customer: Account<'info, Customer>,
+  #[account(
+    seeds = [
+      PROMOTER_TAG,
+      b"_",
+      referrer.as_ref()
+    ],
+    bump
+  )]
+  pub referrer_promoter: Option<Account<'info, Promoter>>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::PromoterManager, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
+}
+
+#[derive(Accounts)]
+pub struct Claim<'info> {
+  #[account(
+    seeds = [],
+    bump,
+  )]
+  pub store: Account<'info, Store>,
+  #[account(
+    mut,
+    seeds = [
+      CUSTOMER_TAG,
+      b"_",
+      payer.key().as_ref()
+    ],
+    bump
+  )]
+  pub customer: Account<'info, Customer>,
+  pub epoc: Account<'info, Epoc>,
+  #[account(
+    mut,
+    seeds = [
+      ALLOCATION_TAG,
+      b"_",
+      &epoc.get_id().to_le_bytes(),
+      b"_",
+      payer.key().as_ref()
+    ],
+    bump
+  )]
+  pub allocation: Account<'info, Allocation>,
+  #[account(
+    constraint = mint.key() == store.get_mint() @ errors::Store::WrongMint,
+  )]
+  pub mint: Account<'info, Mint>,
+  #[account(
+    mut,
+    seeds = [VAULT_TAG],
+    bump,
+  )]
+  pub vault: Account<'info, TokenAccount>,
+  #[account(
+    mut,
+    constraint = customer_ata.mint == store.get_mint() @ errors::Store::WrongMint,
+    constraint = customer_ata.owner == payer.key(),
+  )]
+  pub customer_ata: Account<'info, TokenAccount>,
+  pub asset_program: Program<'info, Token>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Refund<'info> {
+  #[account(mut)]
+  pub store: Account<'info, Store>,
+  #[account(
+    mut,
+    seeds = [
+      CUSTOMER_TAG,
+      b"_",
+      payer.key().as_ref()
+    ],
+    bump
+  )]
+  pub customer: Account<'info, Customer>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RefundToken<'info> {
+  #[account(
+    seeds = [],
+    bump,
+  )]
+  pub store: Account<'info, Store>,
+  #[account(
+    seeds = [
+      PAYMENT_TOKEN_TAG,
+      b"_",
+      payment_token.get_mint().as_ref()
+    ],
+    bump,
+  )]
+  pub payment_token: Account<'info, PaymentToken>,
+  #[account(
+    address = payment_token.get_mint() @ errors::Store::WrongStablecoin,
+  )]
+  pub mint: InterfaceAccount<'info, token_interface::Mint>,
+  #[account(
+    mut,
+    seeds = [
+      CONTRIBUTION_TAG,
+      b"_",
+      payment_token.get_mint().as_ref(),
+      b"_",
+      payer.key().as_ref()
+    ],
+    bump
+  )]
+  pub contribution: Account<'info, Contribution>,
+  #[account(
+    mut,
+    constraint = customer_ata.mint == payment_token.get_mint() @ errors::Store::WrongStablecoin,
+    constraint = customer_ata.owner == payer.key(),
+  )]
+  pub customer_ata: InterfaceAccount<'info, token_interface::TokenAccount>,
+  #[account(
+    mut,
+    constraint = escrow_ata.mint == payment_token.get_mint() @ errors::Store::WrongEscrow,
+    constraint = escrow_ata.owner == store.key() @ errors::Store::WrongEscrow,
+  )]
+  pub escrow_ata: InterfaceAccount<'info, token_interface::TokenAccount>,
+  pub asset_program: Interface<'info, TokenInterface>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+}
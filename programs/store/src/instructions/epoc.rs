@@ -1,17 +1,21 @@
 use anchor_lang::prelude::*;
+use crate::errors;
+use crate::state::admin::{ Admin, Role };
 use crate::state::epoc::Epoc;
 use crate::state::store::Store;
 
-use crate::config::EPOC_TAG;
+use crate::config::{ ADMIN_TAG, EPOC_TAG };
 
 pub fn init_epoc(
   ctx: Context<InitEpoc>,
   id: i16,
   price: u64,
   total_supply: u128,
+  start_ts: i64,
+  end_ts: i64,
 ) -> Result<()> {
   let epoc = &mut ctx.accounts.epoc;
-  epoc.init(id, price, total_supply)
+  epoc.init(id, price, total_supply, start_ts, end_ts)
 }
 
 pub fn set_epoc_price(
@@ -22,6 +26,33 @@ pub fn set_epoc_price(
   epoc.set_price(price)
 }
 
+pub fn set_epoc_vesting(
+  ctx: Context<SetEpocVesting>,
+  tge_unlock: u64,
+  cliff: i64,
+  duration: i64,
+) -> Result<()> {
+  let epoc = &mut ctx.accounts.epoc;
+  epoc.set_vesting(tge_unlock, cliff, duration)
+}
+
+pub fn set_epoc_wallet_cap(
+  ctx: Context<SetEpocWalletCap>,
+  wallet_max_usd: u64,
+  wallet_max_asset: u128,
+) -> Result<()> {
+  let epoc = &mut ctx.accounts.epoc;
+  epoc.set_wallet_cap(wallet_max_usd, wallet_max_asset)
+}
+
+pub fn set_epoc_merkle_root(
+  ctx: Context<SetEpocMerkleRoot>,
+  merkle_root: [u8; 32],
+) -> Result<()> {
+  let epoc = &mut ctx.accounts.epoc;
+  epoc.set_merkle_root(merkle_root)
+}
+
 pub fn set_epoc_supply(
   ctx: Context<SetEpocSupply>,
   total_supply: u128
@@ -34,7 +65,7 @@ pub fn enable_epoc(
   ctx: Context<SetEpocEnabled>,
 ) -> Result<()> {
   let epoc = &mut ctx.accounts.epoc;
-  epoc.set_enable().unwrap();
+  epoc.set_enable()?;
 
   let store = &mut ctx.accounts.store;
   store.set_epoc(epoc.get_id())
@@ -64,6 +95,12 @@ pub struct InitEpoc<'info> {
   pub epoc: Account<'info, Epoc>,
   #[account(mut)]
   pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::SaleManager, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
   pub system_program: Program<'info, System>,
 }
 
@@ -74,6 +111,57 @@ pub struct SetEpocPrice<'info> {
   pub epoc: Account<'info, Epoc>,
   #[account(mut)]
   pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::SaleManager, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
+}
+
+#[derive(Accounts)]
+#[instruction(tge_unlock: u64, cliff: i64, duration: i64)]
+pub struct SetEpocVesting<'info> {
+  #[account(mut)]
+  pub epoc: Account<'info, Epoc>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::SaleManager, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet_max_usd: u64, wallet_max_asset: u128)]
+pub struct SetEpocWalletCap<'info> {
+  #[account(mut)]
+  pub epoc: Account<'info, Epoc>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::SaleManager, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
+}
+
+#[derive(Accounts)]
+#[instruction(merkle_root: [u8; 32])]
+pub struct SetEpocMerkleRoot<'info> {
+  #[account(mut)]
+  pub epoc: Account<'info, Epoc>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::SaleManager, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
 }
 
 #[derive(Accounts)]
@@ -83,6 +171,12 @@ pub struct SetEpocSupply<'info> {
   pub epoc: Account<'info, Epoc>,
   #[account(mut)]
   pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::SaleManager, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
 }
 
 #[derive(Accounts)]
@@ -93,6 +187,12 @@ pub struct SetEpocEnabled<'info> {
   pub store: Account<'info, Store>,
   #[account(mut)]
   pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::Operator, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
 }
 
 #[derive(Accounts)]
@@ -103,4 +203,10 @@ pub struct SetEpocDisabled<'info> {
   pub store: Account<'info, Store>,
   #[account(mut)]
   pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::Pauser, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
 }
\ No newline at end of file
@@ -0,0 +1,314 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{ self, Transfer as SystemTransfer };
+use anchor_lang::Discriminator;
+use crate::config::{ ADMIN_TAG, ALLOCATION_TAG, CUSTOMER_TAG, EPOC_TAG, PROMOTER_TAG };
+
+use crate::events;
+use crate::errors;
+use crate::state::admin::{ Admin, Role };
+use crate::state::store::Store;
+use crate::state::epoc::Epoc;
+use crate::state::customer::Customer;
+use crate::state::allocation::Allocation;
+use crate::state::promoter::Promoter;
+use crate::state::legacy::LegacyPromoter;
+use crate::instructions::store::credit_bonus;
+
+pub fn migrate_store(
+  ctx: Context<MigrateStore>,
+) -> Result<()> {
+  let store_info = ctx.accounts.store.to_account_info();
+
+  grow(&store_info, &ctx.accounts.payer, &ctx.accounts.system_program, 8 + Store::MAX_SIZE)?;
+
+  let mut store = Store::try_deserialize(&mut &store_info.try_borrow_data()?[..])?;
+  store.migrate()?;
+  store.try_serialize(&mut &mut store_info.try_borrow_mut_data()?[..])?;
+
+  emit!(events::MigrateEvent {
+    account: store_info.key(),
+    size: store_info.data_len() as u64,
+  });
+
+  Ok(())
+}
+
+pub fn migrate_epoc(
+  ctx: Context<MigrateEpoc>,
+  _id: i16,
+) -> Result<()> {
+  let epoc_info = ctx.accounts.epoc.to_account_info();
+
+  grow(&epoc_info, &ctx.accounts.payer, &ctx.accounts.system_program, 8 + Epoc::MAX_SIZE)?;
+
+  let mut epoc = Epoc::try_deserialize(&mut &epoc_info.try_borrow_data()?[..])?;
+  epoc.migrate()?;
+  epoc.try_serialize(&mut &mut epoc_info.try_borrow_mut_data()?[..])?;
+
+  emit!(events::MigrateEvent {
+    account: epoc_info.key(),
+    size: epoc_info.data_len() as u64,
+  });
+
+  Ok(())
+}
+
+pub fn migrate_customer(
+  ctx: Context<MigrateCustomer>,
+  _customer_key: Pubkey,
+) -> Result<()> {
+  let customer_info = ctx.accounts.customer.to_account_info();
+  let allocation = &mut ctx.accounts.allocation;
+
+  grow(&customer_info, &ctx.accounts.payer, &ctx.accounts.system_program, 8 + Customer::MAX_SIZE)?;
+
+  // Legacy purchases become claimable through an allocation on the legacy epoc
+  let mut customer = Customer::try_deserialize(&mut &customer_info.try_borrow_data()?[..])?;
+  allocation.set_asset_amount(customer.get_asset_amount())?;
+
+  emit!(events::MigrateEvent {
+    account: customer_info.key(),
+    size: customer_info.data_len() as u64,
+  });
+
+  Ok(())
+}
+
+pub fn migrate_promoter(
+  ctx: Context<MigratePromoter>,
+  promoter_key: Pubkey,
+) -> Result<()> {
+  let promoter_info = ctx.accounts.promoter.to_account_info();
+  let promoter_wallet = &ctx.accounts.promoter_wallet;
+
+  let legacy = {
+    let data = promoter_info.try_borrow_data()?;
+    if data.len() != 8 + LegacyPromoter::MAX_SIZE {
+      return err!(errors::Store::AccountMigrated);
+    }
+
+    if data[..8] != Promoter::DISCRIMINATOR {
+      return err!(ErrorCode::AccountDiscriminatorMismatch);
+    }
+
+    LegacyPromoter::deserialize(&mut &data[8..])?
+  };
+
+  // Legacy SOL fees sit in the promoter account lamports, they are paid out instead of being credited again
+  if legacy.sol_amount > 0 {
+    promoter_info.sub_lamports(legacy.sol_amount)?;
+    promoter_wallet.add_lamports(legacy.sol_amount)?;
+
+    emit!(events::WithdrawSolEvent {
+      promoter: promoter_wallet.key(),
+      amount: legacy.sol_amount,
+    });
+  }
+
+  grow(&promoter_info, &ctx.accounts.payer, &ctx.accounts.system_program, 8 + Promoter::MAX_SIZE)?;
+
+  let promoter = Promoter::from_legacy(&legacy);
+  promoter.try_serialize(&mut &mut promoter_info.try_borrow_mut_data()?[..])?;
+
+  // Legacy bonuses become claimable through a promoter allocation on the legacy epoc
+  credit_bonus(
+    ctx.accounts.store.get_legacy_epoc(),
+    &ctx.accounts.promoter_allocation,
+    &ctx.accounts.payer.to_account_info(),
+    &ctx.accounts.system_program.to_account_info(),
+    promoter_key,
+    ctx.bumps.promoter_allocation,
+    legacy.asset_amount,
+  )?;
+
+  emit!(events::MigrateEvent {
+    account: promoter_info.key(),
+    size: promoter_info.data_len() as u64,
+  });
+
+  Ok(())
+}
+
+// Grows an account created with an older layout, the payer covering the extra rent
+fn grow<'info>(
+  account_info: &AccountInfo<'info>,
+  payer: &Signer<'info>,
+  system_program: &Program<'info, System>,
+  space: usize,
+)
+  -> Result<()>
+{
+  if account_info.data_len() >= space {
+    return err!(errors::Store::AccountMigrated);
+  }
+
+  let lamports = Rent::get()?.minimum_balance(space).saturating_sub(account_info.lamports());
+  if lamports > 0 {
+    let cpi_accounts = SystemTransfer {
+      from: payer.to_account_info(),
+      to: account_info.clone(),
+    };
+    system_program::transfer(CpiContext::new(system_program.to_account_info(), cpi_accounts), lamports)?;
+  }
+
+  account_info.realloc(space, true)?;
+
+  Ok(())
+}
+
+#[derive(Accounts)]
+pub struct MigrateStore<'info> {
+  /// CHECK : Legacy layout, deserialized once grown to the current size
+  #[account(
+    mut,
+    seeds = [],
+    bump,
+    owner = crate::ID,
+  )]
+  pub store: UncheckedAccount<'info>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::Operator, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
+  pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(id: i16)]
+pub struct MigrateEpoc<'info> {
+  /// CHECK : Legacy layout, deserialized once grown to the current size
+  #[account(
+    mut,
+    seeds = [
+      EPOC_TAG,
+      b"_",
+      &id.to_le_bytes()
+    ],
+    bump,
+    owner = crate::ID,
+  )]
+  pub epoc: UncheckedAccount<'info>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::Operator, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
+  pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(customer_key: Pubkey)]
+pub struct MigrateCustomer<'info> {
+  /// CHECK : Legacy layout, deserialized once grown to the current size
+  #[account(
+    mut,
+    seeds = [
+      CUSTOMER_TAG,
+      b"_",
+      customer_key.as_ref()
+    ],
+    bump,
+    owner = crate::ID,
+  )]
+  pub customer: UncheckedAccount<'info>,
+  #[account(
+    seeds = [],
+    bump,
+  )]
+  pub store: Account<'info, Store>,
+  #[account(
+    init,
+    payer = payer,
+    space = 8 + Allocation::MAX_SIZE,
+    seeds = [
+      ALLOCATION_TAG,
+      b"_",
+      &store.get_legacy_epoc().to_le_bytes(),
+      b"_",
+      customer_key.as_ref()
+    ],
+    bump
+  )]
+  pub allocation: Account<'info, Allocation>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+  pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(promoter_key: Pubkey)]
+pub struct MigratePromoter<'info> {
+  /// CHECK : Legacy layout, rewritten in the current layout
+  #[account(
+    mut,
+    seeds = [
+      PROMOTER_TAG,
+      b"_",
+      promoter_key.as_ref()
+    ],
+    bump,
+    owner = crate::ID,
+  )]
+  pub promoter: UncheckedAccount<'info>,
+  /// CHECK : Receives the legacy SOL fees, checked against the promoter key
+  #[account(
+    mut,
+    address = promoter_key,
+  )]
+  pub promoter_wallet: AccountInfo<'info>,
+  #[account(
+    seeds = [],
+    bump,
+  )]
+  pub store: Account<'info, Store>,
+  /// CHECK : Created when the promoter has a legacy bonus
+  #[account(
+    mut,
+    seeds = [
+      PROMOTER_TAG,
+      b"_",
+      ALLOCATION_TAG,
+      b"_",
+      &store.get_legacy_epoc().to_le_bytes(),
+      b"_",
+      promoter_key.as_ref()
+    ],
+    bump
+  )]
+  pub promoter_allocation: UncheckedAccount<'info>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+  pub system_program: Program<'info, System>,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::state::fixtures::zeroed;
+  use crate::state::epoc::tests::epoc;
+
+  #[test]
+  fn migrated_customer_can_claim() {
+    // Legacy layout: the discriminator followed by the purchased asset amount only
+    let mut data = Customer::DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&1_500_000_000u128.to_le_bytes());
+    data.resize(8 + Customer::MAX_SIZE, 0);
+
+    let mut customer = Customer::try_deserialize(&mut &data[..]).unwrap();
+    let mut allocation: Allocation = zeroed();
+    allocation.set_asset_amount(customer.get_asset_amount()).unwrap();
+
+    let mut legacy_epoc = epoc(0, 0, 0);
+    legacy_epoc.migrate().unwrap();
+
+    assert_eq!(allocation.release(&legacy_epoc, 1_000, 999, 6).unwrap(), (0, 0));
+    assert_eq!(allocation.release(&legacy_epoc, 1_000, 1_000, 6).unwrap(), (1_500_000_000, 1_500_000));
+  }
+}
\ No newline at end of file
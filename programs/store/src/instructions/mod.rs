@@ -1,6 +1,16 @@
 pub use store::*;
 pub use epoc::*;
 pub use promoter::*;
+pub use customer::*;
+pub use admin::*;
+pub use payment_token::*;
+pub use reference_price::*;
+pub use migration::*;
 pub mod store;
 pub mod epoc;
-pub mod promoter;
\ No newline at end of file
+pub mod promoter;
+pub mod customer;
+pub mod admin;
+pub mod payment_token;
+pub mod reference_price;
+pub mod migration;
\ No newline at end of file
@@ -0,0 +1,150 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{ Mint, TokenAccount, TokenInterface };
+use crate::config::{ ADMIN_TAG, ESCROW_TAG, PAYMENT_TOKEN_TAG };
+
+use crate::events;
+use crate::errors;
+use crate::state::admin::{ Admin, Role };
+use crate::state::store::Store;
+use crate::state::payment_token::*;
+
+pub fn init_payment_token(
+  ctx: Context<InitPaymentToken>,
+  feed_id: [u8; 32],
+  price: u64,
+  peg_tolerance_bps: u64,
+) -> Result<()> {
+  let payment_token = &mut ctx.accounts.payment_token;
+  let mint = &ctx.accounts.mint;
+
+  payment_token.init(mint.key(), mint.decimals, feed_id, price, peg_tolerance_bps)?;
+
+  emit!(events::SetPaymentTokenPriceEvent {
+    mint: mint.key(),
+    feed_id: feed_id,
+    price: price,
+    peg_tolerance_bps: peg_tolerance_bps,
+  });
+
+  Ok(())
+}
+
+pub fn set_payment_token_price(
+  ctx: Context<SetPaymentTokenPrice>,
+  feed_id: [u8; 32],
+  price: u64,
+  peg_tolerance_bps: u64,
+) -> Result<()> {
+  let payment_token = &mut ctx.accounts.payment_token;
+
+  payment_token.set_price(feed_id, price, peg_tolerance_bps)?;
+
+  emit!(events::SetPaymentTokenPriceEvent {
+    mint: payment_token.get_mint(),
+    feed_id: feed_id,
+    price: price,
+    peg_tolerance_bps: peg_tolerance_bps,
+  });
+
+  Ok(())
+}
+
+pub fn enable_payment_token(
+  ctx: Context<SetPaymentTokenEnabled>,
+) -> Result<()> {
+  let payment_token = &mut ctx.accounts.payment_token;
+  payment_token.enable()
+}
+
+pub fn disable_payment_token(
+  ctx: Context<SetPaymentTokenDisabled>,
+) -> Result<()> {
+  let payment_token = &mut ctx.accounts.payment_token;
+  payment_token.disable()
+}
+
+#[derive(Accounts)]
+#[instruction(feed_id: [u8; 32], price: u64, peg_tolerance_bps: u64)]
+pub struct InitPaymentToken<'info> {
+  pub store: Account<'info, Store>,
+  pub mint: InterfaceAccount<'info, Mint>,
+  #[account(
+    init,
+    payer = payer,
+    space = 8 + PaymentToken::MAX_SIZE,
+    seeds = [
+      PAYMENT_TOKEN_TAG,
+      b"_",
+      mint.key().as_ref()
+    ],
+    bump,
+  )]
+  pub payment_token: Account<'info, PaymentToken>,
+  #[account(
+    init,
+    payer = payer,
+    seeds = [
+      ESCROW_TAG,
+      b"_",
+      mint.key().as_ref()
+    ],
+    bump,
+    token::mint = mint,
+    token::authority = store,
+    token::token_program = asset_program,
+  )]
+  pub escrow_ata: InterfaceAccount<'info, TokenAccount>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::Operator, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
+  pub asset_program: Interface<'info, TokenInterface>,
+  pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(feed_id: [u8; 32], price: u64, peg_tolerance_bps: u64)]
+pub struct SetPaymentTokenPrice<'info> {
+  #[account(mut)]
+  pub payment_token: Account<'info, PaymentToken>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::Operator, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
+}
+
+#[derive(Accounts)]
+pub struct SetPaymentTokenEnabled<'info> {
+  #[account(mut)]
+  pub payment_token: Account<'info, PaymentToken>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::Operator, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
+}
+
+#[derive(Accounts)]
+pub struct SetPaymentTokenDisabled<'info> {
+  #[account(mut)]
+  pub payment_token: Account<'info, PaymentToken>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::Pauser, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
+}
\ No newline at end of file
@@ -1,9 +1,21 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer as SplTransfer};
-use crate::config::{ USDC, USDT, PROMOTER_TAG };
+use anchor_spl::token::{ self, Mint, Token, TokenAccount, Transfer as SplTransfer };
+use anchor_spl::token_interface::{ self, TokenInterface };
+use crate::config::{
+  ADMIN_TAG, PROMOTER_TAG, PAYMENT_TOKEN_TAG, ALLOCATION_TAG, VAULT_TAG,
+  REFERRAL_CODE_TAG
+};
 
 use crate::events;
 use crate::errors;
+use crate::instructions::store::{ transfer_from_escrow, transfer_from_vault };
+use crate::state::admin::{ Admin, Role };
+use crate::state::store::Store;
+use crate::state::epoc::Epoc;
+use crate::state::allocation::Allocation;
+use crate::state::payment_token::PaymentToken;
+use crate::state::promoter_balance::PromoterBalance;
+use crate::state::referral_code::ReferralCode;
 use crate::state::promoter::*;
 
 pub fn init_promoter(
@@ -15,6 +27,45 @@ pub fn init_promoter(
   promoter.init(first_fee, second_fee)
 }
 
+pub fn register_promoter(
+  ctx: Context<RegisterPromoter>,
+  code: String,
+) -> Result<()> {
+  let payer = &ctx.accounts.payer;
+  let promoter = &mut ctx.accounts.promoter;
+  let referral_code = &mut ctx.accounts.referral_code;
+
+  // Self-registered promoters earn the store default fees
+  if !promoter.is_initialized() {
+    promoter.init(0, 0)?;
+  }
+
+  referral_code.init(&code, payer.key())?;
+
+  emit!(events::RegisterPromoterEvent {
+    promoter: payer.key(),
+    code: code,
+  });
+
+  Ok(())
+}
+
+pub fn revoke_referral_code(
+  ctx: Context<RevokeReferralCode>,
+  code: String,
+) -> Result<()> {
+  let referral_code = &mut ctx.accounts.referral_code;
+
+  referral_code.revoke()?;
+
+  emit!(events::RevokeReferralCodeEvent {
+    promoter: referral_code.get_promoter(),
+    code: code,
+  });
+
+  Ok(())
+}
+
 pub fn set_promoter_fee(
   ctx: Context<SetPromoterReward>,
   first_fee: u64,
@@ -24,6 +75,26 @@ pub fn set_promoter_fee(
   promoter.set_fee(first_fee, second_fee)
 }
 
+pub fn set_promoter_upline(
+  ctx: Context<SetPromoterUpline>,
+  promoter_key: Pubkey,
+  upline: Pubkey,
+) -> Result<()> {
+  let promoter = &mut ctx.accounts.promoter;
+
+  if upline == promoter_key {
+    return err!(errors::Store::SelfReferral);
+  }
+
+  // The upline must be a registered promoter, an empty key clears it
+  let registered = ctx.accounts.upline_promoter.as_ref().is_some_and(|upline_promoter| upline_promoter.is_initialized());
+  if upline != Pubkey::default() && !registered {
+    return err!(errors::Store::PromoterWrongUpline);
+  }
+
+  promoter.set_upline(upline)
+}
+
 pub fn enable_promoter(
   ctx: Context<SetPromoterEnabled>,
 ) -> Result<()> {
@@ -42,14 +113,19 @@ pub fn withdraw_sol(
   ctx: Context<Withdraw>,
 ) -> Result<()> {
   let payer = &mut ctx.accounts.payer;
+  let store = &mut ctx.accounts.store;
   let promoter = &mut ctx.accounts.promoter;
-  
+
+  if !store.is_soft_cap_reached() {
+    return err!(errors::Store::StoreSoftCapNotReached);
+  }
+
   let sol_fee = promoter.get_sol_fee_amount();
   if sol_fee > 0 {
-    promoter.reset_sol_fee_amount().unwrap();
+    promoter.reset_sol_fee_amount()?;
 
-    promoter.sub_lamports(sol_fee).unwrap();
-    payer.add_lamports(sol_fee).unwrap();
+    store.sub_lamports(sol_fee)?;
+    payer.add_lamports(sol_fee)?;
 
     emit!(events::WithdrawSolEvent {
       promoter: payer.key(),
@@ -60,65 +136,60 @@ pub fn withdraw_sol(
   Ok(())
 }
 
-pub fn withdraw_usdc(
-  ctx: Context<WithdrawUSDC>,
+pub fn withdraw_token(
+  ctx: Context<WithdrawToken>,
 ) -> Result<()> {
   let payer = &mut ctx.accounts.payer;
-  let promoter = &mut ctx.accounts.promoter;
-  
+  let store = &ctx.accounts.store;
+  let payment_token = &ctx.accounts.payment_token;
+  let promoter_balance = &mut ctx.accounts.promoter_balance;
+
+  let mint = &ctx.accounts.mint;
   let promoter_ata = &ctx.accounts.promoter_ata;
-  let promoter_pda_ata = &ctx.accounts.promoter_pda_ata;
+  let escrow_ata = &ctx.accounts.escrow_ata;
   let program = &ctx.accounts.asset_program;
 
-  let amount = promoter.get_usdc_amount();
+  if !store.is_soft_cap_reached() {
+    return err!(errors::Store::StoreSoftCapNotReached);
+  }
+
+  let amount = promoter_balance.get_amount();
   if amount == 0 {
     return err!(errors::Store::PromoterNoFunds);
   }
 
-  promoter.reset_usdc_amount().unwrap();
+  promoter_balance.reset_amount()?;
 
-  let payer_key = payer.key();
-  let bump = &[ctx.bumps.promoter];
-  let seeds: &[&[u8]] = &[PROMOTER_TAG, b"_", payer_key.as_ref(), bump];
-  let signer_seeds = &[&seeds[..]];
-
-  let cpi_accounts = SplTransfer {
-    from: promoter_pda_ata.to_account_info(),
-    to: promoter_ata.to_account_info(),
-    authority: promoter.to_account_info(),
-  };
-  let ctx = CpiContext::new_with_signer(program.to_account_info(), cpi_accounts, signer_seeds);
-  token::transfer(ctx, amount).unwrap();
+  transfer_from_escrow(store, escrow_ata, promoter_ata, mint, program, ctx.bumps.store, amount)?;
 
-  emit!(events::WithdrawUsdcEvent {
+  emit!(events::WithdrawTokenEvent {
     promoter: payer.key(),
+    mint: payment_token.get_mint(),
     amount: amount,
   });
 
   Ok(())
 }
 
-pub fn withdraw_usdt(
-  ctx: Context<WithdrawUSDT>,
+// Stablecoin fees earned before the escrow upgrade are held by the promoter account's own token accounts
+pub fn withdraw_legacy_token(
+  ctx: Context<WithdrawLegacyToken>,
 ) -> Result<()> {
-  let payer = &mut ctx.accounts.payer;
-  let promoter = &mut ctx.accounts.promoter;
-  
-  let promoter_ata = &ctx.accounts.promoter_ata;
+  let payer = &ctx.accounts.payer;
+  let promoter = &ctx.accounts.promoter;
   let promoter_pda_ata = &ctx.accounts.promoter_pda_ata;
+  let promoter_ata = &ctx.accounts.promoter_ata;
   let program = &ctx.accounts.asset_program;
 
-  let amount = promoter.get_usdt_amount();
+  let amount = promoter_pda_ata.amount;
   if amount == 0 {
     return err!(errors::Store::PromoterNoFunds);
   }
 
-  promoter.reset_usdt_amount().unwrap();
-
   let payer_key = payer.key();
   let bump = &[ctx.bumps.promoter];
   let seeds: &[&[u8]] = &[PROMOTER_TAG, b"_", payer_key.as_ref(), bump];
-  let signer_seeds = &[&seeds[..]];
+  let signer_seeds = &[seeds];
 
   let cpi_accounts = SplTransfer {
     from: promoter_pda_ata.to_account_info(),
@@ -126,10 +197,46 @@ pub fn withdraw_usdt(
     authority: promoter.to_account_info(),
   };
   let ctx = CpiContext::new_with_signer(program.to_account_info(), cpi_accounts, signer_seeds);
-  token::transfer(ctx, amount).unwrap();
+  token::transfer(ctx, amount)?;
+
+  emit!(events::WithdrawTokenEvent {
+    promoter: payer.key(),
+    mint: promoter_pda_ata.mint,
+    amount: amount,
+  });
+
+  Ok(())
+}
+
+pub fn claim_bonus(
+  ctx: Context<ClaimBonus>,
+) -> Result<()> {
+  let payer = &ctx.accounts.payer;
+  let store = &ctx.accounts.store;
+  let epoc = &ctx.accounts.epoc;
+  let promoter_allocation = &mut ctx.accounts.promoter_allocation;
 
-  emit!(events::WithdrawUsdtEvent {
+  let mint = &ctx.accounts.mint;
+  let vault = &ctx.accounts.vault;
+  let promoter_ata = &ctx.accounts.promoter_ata;
+  let program = &ctx.accounts.asset_program;
+
+  if !store.is_soft_cap_reached() {
+    return err!(errors::Store::StoreSoftCapNotReached);
+  }
+
+  let current_timestamp = Clock::get()?.unix_timestamp;
+  let (asset_amount, amount) = promoter_allocation.release(epoc, store.get_tge(), current_timestamp, mint.decimals)?;
+  if amount == 0 {
+    return err!(errors::Store::PromoterNoFunds);
+  }
+
+  transfer_from_vault(store, vault, promoter_ata, program, ctx.bumps.store, amount)?;
+
+  emit!(events::ClaimBonusEvent {
+    epoc: epoc.get_id(),
     promoter: payer.key(),
+    asset_amount: asset_amount,
     amount: amount,
   });
 
@@ -153,15 +260,114 @@ pub struct InitPromoter<'info> {
   pub promoter: Account<'info, Promoter>,
   #[account(mut)]
   pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::PromoterManager, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
+  pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(code: String)]
+pub struct RegisterPromoter<'info> {
+  #[account(
+    init_if_needed,
+    payer = payer,
+    space = 8 + Promoter::MAX_SIZE,
+    seeds = [
+      PROMOTER_TAG,
+      b"_",
+      payer.key().as_ref()
+    ],
+    bump
+  )]
+  pub promoter: Account<'info, Promoter>,
+  #[account(
+    init,
+    payer = payer,
+    space = 8 + ReferralCode::MAX_SIZE,
+    seeds = [
+      REFERRAL_CODE_TAG,
+      b"_",
+      code.as_bytes()
+    ],
+    bump
+  )]
+  pub referral_code: Account<'info, ReferralCode>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
   pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+#[instruction(code: String)]
+pub struct RevokeReferralCode<'info> {
+  #[account(
+    mut,
+    seeds = [
+      REFERRAL_CODE_TAG,
+      b"_",
+      code.as_bytes()
+    ],
+    bump
+  )]
+  pub referral_code: Account<'info, ReferralCode>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::PromoterManager, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
+}
+
 #[derive(Accounts)]
 pub struct SetPromoterReward<'info> {
   #[account(mut)]
   pub promoter: Account<'info, Promoter>,
   #[account(mut)]
   pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::PromoterManager, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
+}
+
+#[derive(Accounts)]
+#[instruction(promoter_key: Pubkey, upline: Pubkey)]
+pub struct SetPromoterUpline<'info> {
+  #[account(
+    mut,
+    seeds = [
+      PROMOTER_TAG,
+      b"_",
+      promoter_key.as_ref()
+    ],
+    bump
+  )]
+  pub promoter: Account<'info, Promoter>,
+  #[account(
+    seeds = [
+      PROMOTER_TAG,
+      b"_",
+      upline.as_ref()
+    ],
+    bump
+  )]
+  pub upline_promoter: Option<Account<'info, Promoter>>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::PromoterManager, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
 }
 
 #[derive(Accounts)]
@@ -170,6 +376,12 @@ pub struct SetPromoterEnabled<'info> {
   pub promoter: Account<'info, Promoter>,
   #[account(mut)]
   pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::PromoterManager, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
 }
 
 #[derive(Accounts)]
@@ -178,10 +390,22 @@ pub struct SetPromoterDisabled<'info> {
   pub promoter: Account<'info, Promoter>,
   #[account(mut)]
   pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::PromoterManager, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
 }
 
 #[derive(Accounts)]
 pub struct Withdraw<'info> {
+  #[account(
+    mut,
+    seeds = [],
+    bump,
+  )]
+  pub store: Account<'info, Store>,
   #[account(
     mut,
     seeds = [
@@ -197,58 +421,121 @@ pub struct Withdraw<'info> {
 }
 
 #[derive(Accounts)]
-pub struct WithdrawUSDC<'info> {
+pub struct WithdrawToken<'info> {
+  #[account(
+    seeds = [],
+    bump,
+  )]
+  pub store: Account<'info, Store>,
+  #[account(
+    seeds = [
+      PAYMENT_TOKEN_TAG,
+      b"_",
+      payment_token.get_mint().as_ref()
+    ],
+    bump,
+  )]
+  pub payment_token: Account<'info, PaymentToken>,
+  #[account(
+    address = payment_token.get_mint() @ errors::Store::WrongStablecoin,
+  )]
+  pub mint: InterfaceAccount<'info, token_interface::Mint>,
   #[account(
     mut,
     seeds = [
       PROMOTER_TAG,
       b"_",
-      payer.key().as_ref()
+      payer.key().as_ref(),
+      b"_",
+      payment_token.get_mint().as_ref()
     ],
     bump
   )]
-  pub promoter: Account<'info, Promoter>,
+  pub promoter_balance: Account<'info, PromoterBalance>,
   #[account(
     mut,
-    constraint = promoter_ata.mint == USDC.parse::<Pubkey>().unwrap(),
+    constraint = promoter_ata.mint == payment_token.get_mint() @ errors::Store::WrongStablecoin,
     constraint = promoter_ata.owner == payer.key(),
   )]
-  pub promoter_ata: Account<'info, TokenAccount>,
+  pub promoter_ata: InterfaceAccount<'info, token_interface::TokenAccount>,
+  #[account(
+    mut,
+    constraint = escrow_ata.mint == payment_token.get_mint() @ errors::Store::WrongEscrow,
+    constraint = escrow_ata.owner == store.key() @ errors::Store::WrongEscrow,
+  )]
+  pub escrow_ata: InterfaceAccount<'info, token_interface::TokenAccount>,
+  pub asset_program: Interface<'info, TokenInterface>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+}
+
+
+
+#[derive(Accounts)]
+pub struct WithdrawLegacyToken<'info> {
+  #[account(
+    seeds = [
+      PROMOTER_TAG,
+      b"_",
+      payer.key().as_ref()
+    ],
+    bump
+  )]
+  pub promoter: Account<'info, Promoter>,
   #[account(
     mut,
-    constraint = promoter_pda_ata.mint == USDC.parse::<Pubkey>().unwrap(),
     constraint = promoter_pda_ata.owner == promoter.key(),
   )]
   pub promoter_pda_ata: Account<'info, TokenAccount>,
+  #[account(
+    mut,
+    constraint = promoter_ata.mint == promoter_pda_ata.mint @ errors::Store::WrongStablecoin,
+    constraint = promoter_ata.owner == payer.key(),
+  )]
+  pub promoter_ata: Account<'info, TokenAccount>,
   pub asset_program: Program<'info, Token>,
   #[account(mut)]
   pub payer: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct WithdrawUSDT<'info> {
+pub struct ClaimBonus<'info> {
+  #[account(
+    seeds = [],
+    bump,
+  )]
+  pub store: Account<'info, Store>,
+  pub epoc: Account<'info, Epoc>,
   #[account(
     mut,
     seeds = [
       PROMOTER_TAG,
       b"_",
+      ALLOCATION_TAG,
+      b"_",
+      &epoc.get_id().to_le_bytes(),
+      b"_",
       payer.key().as_ref()
     ],
     bump
   )]
-  pub promoter: Account<'info, Promoter>,
+  pub promoter_allocation: Account<'info, Allocation>,
+  #[account(
+    constraint = mint.key() == store.get_mint() @ errors::Store::WrongMint,
+  )]
+  pub mint: Account<'info, Mint>,
   #[account(
     mut,
-    constraint = promoter_ata.mint == USDT.parse::<Pubkey>().unwrap(),
-    constraint = promoter_ata.owner == payer.key(),
+    seeds = [VAULT_TAG],
+    bump,
   )]
-  pub promoter_ata: Account<'info, TokenAccount>,
+  pub vault: Account<'info, TokenAccount>,
   #[account(
     mut,
-    constraint = promoter_pda_ata.mint == USDT.parse::<Pubkey>().unwrap(),
-    constraint = promoter_pda_ata.owner == promoter.key(),
+    constraint = promoter_ata.mint == store.get_mint() @ errors::Store::WrongMint,
+    constraint = promoter_ata.owner == payer.key(),
   )]
-  pub promoter_pda_ata: Account<'info, TokenAccount>,
+  pub promoter_ata: Account<'info, TokenAccount>,
   pub asset_program: Program<'info, Token>,
   #[account(mut)]
   pub payer: Signer<'info>,
@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+use crate::config::{ ADMIN_TAG, REFERENCE_PRICE_TAG };
+
+use crate::events;
+use crate::errors;
+use crate::state::admin::{ Admin, Role };
+use crate::state::reference_price::ReferencePrice;
+
+pub fn set_reference_price(
+  ctx: Context<SetReferencePrice>,
+  price: u64,
+) -> Result<()> {
+  let reference_price = &mut ctx.accounts.reference_price;
+  let current_timestamp = Clock::get()?.unix_timestamp;
+
+  reference_price.set_price(price, current_timestamp)?;
+
+  emit!(events::SetReferencePriceEvent {
+    price: price,
+    updated_ts: current_timestamp,
+  });
+
+  Ok(())
+}
+
+#[derive(Accounts)]
+#[instruction(price: u64)]
+pub struct SetReferencePrice<'info> {
+  #[account(
+    init_if_needed,
+    payer = payer,
+    space = 8 + ReferencePrice::MAX_SIZE,
+    seeds = [REFERENCE_PRICE_TAG],
+    bump,
+  )]
+  pub reference_price: Account<'info, ReferencePrice>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::Operator, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
+  pub system_program: Program<'info, System>,
+}
\ No newline at end of file
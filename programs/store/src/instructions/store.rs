@@ -2,21 +2,34 @@ use anchor_lang::{
   prelude::*,
   solana_program::{ program::invoke, system_instruction::transfer },
 };
-use anchor_spl::token::{ self, Token, TokenAccount, Transfer as SplTransfer };
-use pyth_sdk_solana::{ load_price_feed_from_account_info, PriceFeed, Price };
+use anchor_lang::system_program::{ self, Allocate, Assign, CreateAccount, Transfer as SystemTransfer };
+use anchor_lang::Discriminator;
+use anchor_spl::token::{ self, Mint, Token, TokenAccount, Transfer as SplTransfer };
+use anchor_spl::token_interface::{ self, TokenInterface, TransferChecked };
+use pyth_solana_receiver_sdk::error::GetPriceError;
+use pyth_solana_receiver_sdk::price_update::{ Price, PriceUpdateV2, VerificationLevel };
 use std::str::FromStr;
 
 use crate::errors;
 use crate::events;
+use crate::math::{ add, mul_div, mul_div_ceil, sub, to_u64 };
+use crate::state::admin::{ Admin, Role };
 use crate::state::store::*;
 use crate::state::epoc::Epoc;
 use crate::state::promoter::Promoter;
 use crate::state::customer::Customer;
+use crate::state::allocation::Allocation;
+use crate::state::contribution::Contribution;
+use crate::state::payment_token::PaymentToken;
+use crate::state::promoter_balance::PromoterBalance;
+use crate::state::reference_price::ReferencePrice;
+use crate::state::referral_code::ReferralCode;
 
 use crate::config::{
-  SOL_USD_PRICEFEED, TREASURY, USDC, USDT,
-  PRECISION, STABLE_PRECISION, PROMOTER_TAG,
-  CUSTOMER_TAG, EMPTY_PROMOTER, STALENESS_THRESHOLD
+  ADMIN_TAG, PRECISION, PROMOTER_TAG,
+  CUSTOMER_TAG, EMPTY_PROMOTER, STALENESS_THRESHOLD, VAULT_TAG,
+  ALLOCATION_TAG, PAYMENT_TOKEN_TAG, CONTRIBUTION_TAG, EPOC_TAG,
+  REFERENCE_PRICE_TAG
 };
 
 pub fn init_store(
@@ -35,6 +48,110 @@ pub fn set_store_cap(
   store.set_cap(max_cap, min_cap)
 }
 
+pub fn set_store_soft_cap(
+  ctx: Context<SetStoreSoftCap>,
+  soft_cap: u64,
+) -> Result<()> {
+  let store = &mut ctx.accounts.store;
+  store.set_soft_cap(soft_cap)
+}
+
+pub fn set_store_treasury(
+  ctx: Context<SetStoreTreasury>,
+  treasury: Pubkey,
+) -> Result<()> {
+  let store = &mut ctx.accounts.store;
+  store.set_treasury(treasury)?;
+
+  emit!(events::SetTreasuryEvent {
+    treasury: treasury,
+  });
+
+  Ok(())
+}
+
+pub fn set_store_price_feed(
+  ctx: Context<SetStorePriceFeed>,
+  feed_id: [u8; 32],
+) -> Result<()> {
+  let store = &mut ctx.accounts.store;
+  store.set_feed_id(feed_id)?;
+
+  emit!(events::SetPriceFeedEvent {
+    feed_id: feed_id,
+  });
+
+  Ok(())
+}
+
+pub fn set_store_wallet_cap(
+  ctx: Context<SetStoreWalletCap>,
+  wallet_max_usd: u64,
+  wallet_max_asset: u128,
+) -> Result<()> {
+  let store = &mut ctx.accounts.store;
+  store.set_wallet_cap(wallet_max_usd, wallet_max_asset)
+}
+
+pub fn set_store_epoc_queue(
+  ctx: Context<SetStoreEpocQueue>,
+  epoc_queue: Vec<i16>,
+) -> Result<()> {
+  let store = &mut ctx.accounts.store;
+  store.set_epoc_queue(epoc_queue)
+}
+
+pub fn set_store_max_conf_ratio(
+  ctx: Context<SetStoreMaxConfRatio>,
+  max_conf_ratio: u64,
+) -> Result<()> {
+  let store = &mut ctx.accounts.store;
+  store.set_max_conf_ratio(max_conf_ratio)
+}
+
+pub fn set_store_circuit_breaker(
+  ctx: Context<SetStoreCircuitBreaker>,
+  max_deviation_bps: u64,
+  max_price_move_bps: u64,
+  price_move_window: u64,
+  reference_max_age: u64,
+) -> Result<()> {
+  let store = &mut ctx.accounts.store;
+  store.set_circuit_breaker(max_deviation_bps, max_price_move_bps, price_move_window, reference_max_age)?;
+
+  emit!(events::SetCircuitBreakerEvent {
+    max_deviation_bps: max_deviation_bps,
+    max_price_move_bps: max_price_move_bps,
+    price_move_window: price_move_window,
+    reference_max_age: reference_max_age,
+  });
+
+  Ok(())
+}
+
+pub fn set_store_promoter_policy(
+  ctx: Context<SetStorePromoterPolicy>,
+  reject_invalid_promoter: bool,
+) -> Result<()> {
+  let store = &mut ctx.accounts.store;
+  store.set_promoter_policy(reject_invalid_promoter)?;
+
+  emit!(events::SetPromoterPolicyEvent {
+    reject_invalid_promoter: reject_invalid_promoter,
+  });
+
+  Ok(())
+}
+
+pub fn set_store_upline_fees(
+  ctx: Context<SetStoreUplineFees>,
+  upline_fees: Vec<u64>,
+  max_total_fee: u64,
+) -> Result<()> {
+  let store = &mut ctx.accounts.store;
+  store.set_upline_fees(upline_fees, max_total_fee)
+}
+
 pub fn set_store_fee(
   ctx: Context<SetStoreReward>,
   first_fee: u64,
@@ -58,369 +175,1446 @@ pub fn disable_store(
   store.set_disable()
 }
 
-pub fn deposit_with_sol(
-  ctx: Context<Deposit>,
-  promoter_key: Pubkey,
+pub fn set_store_tge(
+  ctx: Context<SetStoreTge>,
+  tge_ts: i64,
+) -> Result<()> {
+  let store = &mut ctx.accounts.store;
+  store.set_tge(tge_ts, Clock::get()?.unix_timestamp)
+}
+
+pub fn init_vault(
+  ctx: Context<InitVault>,
+) -> Result<()> {
+  let store = &mut ctx.accounts.store;
+  let mint = &ctx.accounts.mint;
+  store.set_mint(mint.key())
+}
+
+pub fn fund_vault(
+  ctx: Context<FundVault>,
   amount: u64,
 ) -> Result<()> {
-  let to_account_infos = &mut ctx.accounts.to_account_infos();
-  let payer = &mut ctx.accounts.payer;
+  let payer = &ctx.accounts.payer;
+  let store = &ctx.accounts.store;
+  let payer_ata = &ctx.accounts.payer_ata;
+  let vault = &ctx.accounts.vault;
+  let asset_program = &ctx.accounts.asset_program;
+
+  let cpi_accounts = SplTransfer {
+    from: payer_ata.to_account_info(),
+    to: vault.to_account_info(),
+    authority: payer.to_account_info(),
+  };
+  let cpi_program = asset_program.to_account_info();
+  token::transfer(CpiContext::new(cpi_program, cpi_accounts), amount)?;
+
+  emit!(events::FundVaultEvent {
+    mint: store.get_mint(),
+    amount: amount,
+  });
+
+  Ok(())
+}
+
+pub fn release_escrow(
+  ctx: Context<ReleaseEscrow>,
+) -> Result<()> {
   let store = &mut ctx.accounts.store;
-  let epoc = &mut ctx.accounts.epoc;
-  let customer = &mut ctx.accounts.customer;
-  let promoter = &mut ctx.accounts.promoter;
-  let price_info = &ctx.accounts.price_info;
-  let treasury_info = &mut ctx.accounts.treasury_info;
+  let treasury_info = &ctx.accounts.treasury_info;
 
-  if !store.is_enabled() {
-    return err!(errors::Store::StoreNotEnabled);
+  if !store.is_soft_cap_reached() {
+    return err!(errors::Store::StoreSoftCapNotReached);
   }
 
-  if !epoc.is_enabled() {
-    return err!(errors::Store::EpocNotEnabled);
-  }
+  let sol_amount = store.get_sol_amount();
+  if sol_amount > 0 {
+    store.reset_sol_amount()?;
 
-  if store.get_epoc() != epoc.get_id() {
-    return err!(errors::Store::InactiveEpoc);
+    store.sub_lamports(sol_amount)?;
+    treasury_info.add_lamports(sol_amount)?;
   }
 
-  if Pubkey::from_str(TREASURY) != Ok(treasury_info.key()){
-    return Err(error!(errors::Store::WrongTreasury))
-  };
+  emit!(events::ReleaseEvent {
+    sol_amount: sol_amount,
+  });
 
-  if Pubkey::from_str(SOL_USD_PRICEFEED) != Ok(price_info.key()){
-    return Err(error!(errors::Store::WrongPriceFeedId))
-  };
-  
-  let (price, expo) = get_price(&price_info).unwrap();
-  let usd_amount = u128::from(amount) * price / 10u128.pow(expo);
-  let asset_amount = usd_amount * 10u128.pow(PRECISION) / u128::from(epoc.get_price());
+  Ok(())
+}
 
-  if store.get_max_cap() < usd_amount {
-    return err!(errors::Store::StoreMaxCapExceeded);
-  }
+pub fn release_token(
+  ctx: Context<ReleaseToken>,
+) -> Result<()> {
+  let store = &ctx.accounts.store;
+  let payment_token = &mut ctx.accounts.payment_token;
+  let mint = &ctx.accounts.mint;
+  let escrow_ata = &ctx.accounts.escrow_ata;
+  let treasury_ata = &ctx.accounts.treasury_ata;
+  let asset_program = &ctx.accounts.asset_program;
 
-  if store.get_min_cap() > usd_amount {
-    return err!(errors::Store::StoreMinCapNotReached);
+  if !store.is_soft_cap_reached() {
+    return err!(errors::Store::StoreSoftCapNotReached);
   }
 
-  if epoc.get_total_sold() + asset_amount > epoc.get_total_supply() {
-    return err!(errors::Store::EpocSupplyExceeded);
-  }
-  
-  let (promoter_sol_amount, promoter_asset_amount) = get_fee(store, promoter_key, promoter, amount, asset_amount).unwrap();
-  let mut to_amount = amount;
-  if promoter_sol_amount > 0 {
-    to_amount = to_amount - promoter_sol_amount;
+  let amount = payment_token.get_amount();
+  if amount > 0 {
+    payment_token.reset_amount()?;
+    transfer_from_escrow(store, escrow_ata, treasury_ata, mint, asset_program, ctx.bumps.store, amount)?;
   }
 
-  let instruction = &transfer(&payer.key(), &treasury_info.key(), to_amount);
-  invoke(instruction, to_account_infos).unwrap();
+  emit!(events::ReleaseTokenEvent {
+    mint: payment_token.get_mint(),
+    amount: amount,
+  });
 
-  if promoter_sol_amount > 0 {
-    let instruction = &transfer(&payer.key(), &promoter.key(), promoter_sol_amount);
-    invoke(instruction, to_account_infos).unwrap();
-  }
+  Ok(())
+}
 
-  // Updating store details
-  store.set_total_sold(asset_amount).unwrap();
+pub fn deposit_with_sol<'info>(
+  ctx: Context<'_, '_, 'info, 'info, Deposit<'info>>,
+  args: DepositArgs,
+  max_price_age: u64,
+) -> Result<()> {
+  let to_account_infos = &ctx.accounts.to_account_infos();
+  let accounts = &mut *ctx.accounts;
+  let mut sale = Sale {
+    store: &mut accounts.store,
+    payer: &accounts.payer,
+    epoc: &mut accounts.epoc,
+    customer: &mut accounts.customer,
+    allocation: &mut accounts.allocation,
+    next_epoc: &mut accounts.next_epoc,
+    next_allocation: &mut accounts.next_allocation,
+    referral_code: &accounts.referral_code,
+    promoter: &accounts.promoter,
+    promoter_allocation: accounts.promoter_allocation.to_account_info(),
+    next_promoter_allocation: accounts.next_promoter_allocation.to_account_info(),
+    system_program: accounts.system_program.to_account_info(),
+    remaining_accounts: ctx.remaining_accounts,
+    promoter_allocation_bump: ctx.bumps.promoter_allocation,
+    next_promoter_allocation_bump: ctx.bumps.next_promoter_allocation,
+  };
 
-  // Updating epoc details
-  epoc.set_total_sold(asset_amount).unwrap();
+  let current_timestamp = Clock::get()?.unix_timestamp;
+  sale.check(current_timestamp)?;
+
+  let price = get_sol_price(sale.store, &accounts.price_update, &accounts.reference_price, max_price_age)?;
+  let usd_amount = mul_div(u128::from(args.amount), u128::from(price), 10u128.pow(PRECISION))?;
+  let quote = sale.quote(&args, current_timestamp)?;
+
+  // Only the fillable share of the deposit is charged, rounded against the buyer
+  let (amount, usd_amount) = if usd_amount > quote.available_usd_amount {
+    if !args.allow_partial || quote.available_usd_amount == 0 {
+      return err!(errors::Store::EpocSupplyExceeded);
+    }
+    (get_partial_amount(args.amount, usd_amount, quote.available_usd_amount, true)?, quote.available_usd_amount)
+  } else {
+    (args.amount, usd_amount)
+  };
+
+  let fill = sale.fill(&args, &quote, usd_amount, amount, None)?;
+
+  let instruction = &transfer(&accounts.payer.key(), &accounts.store.key(), amount);
+  invoke(instruction, to_account_infos)?;
+
+  // Updating store details
+  accounts.store.set_sol_amount(fill.to_amount)?;
 
   // Updating customer details
-  customer.set_asset_amount(asset_amount).unwrap();
+  accounts.customer.set_sol_amount(amount)?;
 
   // Updating promoter details
-  if Pubkey::from_str(EMPTY_PROMOTER) != Ok(promoter_key){
-    promoter.set_sol_fee_amount(promoter_sol_amount).unwrap();
-    promoter.set_asset_amount(promoter_asset_amount).unwrap();
+  if Pubkey::from_str(EMPTY_PROMOTER) != Ok(quote.promoter_key){
+    let promoter = accounts.promoter.as_mut().ok_or(errors::Store::PromoterNotRegistered)?;
+    promoter.set_sol_fee_amount(fill.promoter_amount)?;
   };
 
   emit!(events::DepositWithSolEvent {
-    epoc: epoc.get_id(),
-    customer: payer.key(),
-    promoter: promoter_key,
+    epoc: accounts.epoc.get_id(),
+    customer: accounts.payer.key(),
+    promoter: quote.promoter_key,
     sol_amount: amount,
-    asset_amount: asset_amount,
+    asset_amount: fill.asset_amount,
   });
   Ok(())
 }
 
-pub fn deposit_with_usdc(
-  ctx: Context<DepositUSDC>,
-  promoter_key: Pubkey,
-  amount: u64,
+pub fn deposit_with_token<'info>(
+  ctx: Context<'_, '_, 'info, 'info, DepositToken<'info>>,
+  args: DepositArgs,
 ) -> Result<()> {
-  let payer = &mut ctx.accounts.payer;
-  let store = &mut ctx.accounts.store;
-  let epoc = &mut ctx.accounts.epoc;
-  let customer = &mut ctx.accounts.customer;
-  let promoter = &mut ctx.accounts.promoter;
+  let accounts = &mut *ctx.accounts;
+  let mut sale = Sale {
+    store: &mut accounts.store,
+    payer: &accounts.payer,
+    epoc: &mut accounts.epoc,
+    customer: &mut accounts.customer,
+    allocation: &mut accounts.allocation,
+    next_epoc: &mut accounts.next_epoc,
+    next_allocation: &mut accounts.next_allocation,
+    referral_code: &accounts.referral_code,
+    promoter: &accounts.promoter,
+    promoter_allocation: accounts.promoter_allocation.to_account_info(),
+    next_promoter_allocation: accounts.next_promoter_allocation.to_account_info(),
+    system_program: accounts.system_program.to_account_info(),
+    remaining_accounts: ctx.remaining_accounts,
+    promoter_allocation_bump: ctx.bumps.promoter_allocation,
+    next_promoter_allocation_bump: ctx.bumps.next_promoter_allocation,
+  };
+  let payment_token = &mut accounts.payment_token;
 
-  let customer_ata = &ctx.accounts.customer_ata;
-  let treasury_ata = &ctx.accounts.treasury_ata;
-  let promoter_pda_ata = &ctx.accounts.promoter_pda_ata;
-  let asset_program = &ctx.accounts.asset_program;
+  let mint = &accounts.mint;
+  let customer_ata = &accounts.customer_ata;
+  let escrow_ata = &mut accounts.escrow_ata;
+  let asset_program = &accounts.asset_program;
 
-  if !store.is_enabled() {
-    return err!(errors::Store::StoreNotEnabled);
-  }
+  let current_timestamp = Clock::get()?.unix_timestamp;
+  sale.check(current_timestamp)?;
 
-  if !epoc.is_enabled() {
-    return err!(errors::Store::EpocNotEnabled);
+  if !payment_token.is_enabled() {
+    return err!(errors::Store::PaymentTokenNotEnabled);
   }
 
-  if store.get_epoc() != epoc.get_id() {
-    return err!(errors::Store::InactiveEpoc);
-  }
+  let decimals = u32::from(payment_token.get_decimals());
+  let price = if payment_token.has_feed_id() {
+    let price_update = accounts.price_update.as_ref().ok_or(errors::Store::WrongPriceFeedId)?;
+    let (price, expo) = get_price(price_update, &payment_token.get_feed_id(), STALENESS_THRESHOLD, sale.store.get_max_conf_ratio())?;
+    let oracle_price = to_u64(mul_div(price, 10u128.pow(PRECISION), 10u128.pow(expo))?)?;
 
-  let usd_amount = u128::from(amount) * 10u128.pow(STABLE_PRECISION);
-  let asset_amount = usd_amount * 10u128.pow(PRECISION) / u128::from(epoc.get_price());
+    // A pegged token is still sold at its peg, but only while the oracle agrees within tolerance
+    if payment_token.get_price() > 0 && payment_token.is_depegged(oracle_price) {
+      return err!(errors::Store::PaymentTokenDepegged);
+    }
 
-  if store.get_max_cap() < usd_amount {
-    return err!(errors::Store::StoreMaxCapExceeded);
-  }
+    if payment_token.get_price() > 0 { payment_token.get_price() } else { oracle_price }
+  } else {
+    payment_token.get_price()
+  };
 
-  if store.get_min_cap() > usd_amount {
-    return err!(errors::Store::StoreMinCapNotReached);
-  }
+  let usd_amount = mul_div(u128::from(args.amount), u128::from(price), 10u128.pow(decimals))?;
+  let quote = sale.quote(&args, current_timestamp)?;
+
+  // Only the fillable share is pulled from the customer, rounded down so that it always fits
+  let amount = if usd_amount > quote.available_usd_amount {
+    if !args.allow_partial {
+      return err!(errors::Store::EpocSupplyExceeded);
+    }
+    get_partial_amount(args.amount, usd_amount, quote.available_usd_amount, false)?
+  } else {
+    args.amount
+  };
 
-  if epoc.get_total_sold() + asset_amount > epoc.get_total_supply() {
+  if amount == 0 {
     return err!(errors::Store::EpocSupplyExceeded);
   }
 
-  let (promoter_stable_fee_amount, promoter_asset_amount) = get_fee(store, promoter_key, promoter, amount, asset_amount).unwrap();
-  let mut to_amount = amount;
-  if promoter_stable_fee_amount > 0 {
-    to_amount = to_amount - promoter_stable_fee_amount;
-  }
+  let escrow_amount = escrow_ata.amount;
 
-  let cpi_accounts = SplTransfer {
+  let cpi_accounts = TransferChecked {
     from: customer_ata.to_account_info(),
-    to: treasury_ata.to_account_info(),
-    authority: payer.to_account_info(),
+    mint: mint.to_account_info(),
+    to: escrow_ata.to_account_info(),
+    authority: sale.payer.to_account_info(),
   };
   let cpi_program = asset_program.to_account_info();
-  token::transfer(CpiContext::new(cpi_program, cpi_accounts), to_amount).unwrap();
-  
-  if promoter_stable_fee_amount > 0 {
-    let cpi_accounts = SplTransfer {
-      from: customer_ata.to_account_info(),
-      to: promoter_pda_ata.to_account_info(),
-      authority: payer.to_account_info(),
-    };
-    let cpi_program = asset_program.to_account_info();
-    token::transfer(CpiContext::new(cpi_program, cpi_accounts), promoter_stable_fee_amount).unwrap();
-  }
+  token_interface::transfer_checked(CpiContext::new(cpi_program, cpi_accounts), amount, mint.decimals)?;
 
-  // Updating store details
-  store.set_total_sold(asset_amount).unwrap();
+  // Transfer fee extensions may withhold part of the amount, so only what reached the escrow is credited
+  escrow_ata.reload()?;
+  let received_amount = escrow_ata.amount.checked_sub(escrow_amount).ok_or(errors::Store::MathOverflow)?;
+  let usd_amount = mul_div(u128::from(received_amount), u128::from(price), 10u128.pow(decimals))?;
+
+  let fill = sale.fill(&args, &quote, usd_amount, received_amount, Some(payment_token.get_mint()))?;
 
-  // Updating epoc details
-  epoc.set_total_sold(asset_amount).unwrap();
+  // Updating payment token details
+  payment_token.set_amount(fill.to_amount)?;
 
   // Updating customer details
-  customer.set_asset_amount(asset_amount).unwrap();
+  accounts.contribution.set_amount(received_amount)?;
 
   // Updating promoter details
-  if Pubkey::from_str(EMPTY_PROMOTER) != Ok(promoter_key){
-    promoter.set_usdc_amount(promoter_stable_fee_amount).unwrap();
-    promoter.set_asset_amount(promoter_asset_amount).unwrap();
+  if Pubkey::from_str(EMPTY_PROMOTER) != Ok(quote.promoter_key){
+    credit_balance(
+      &accounts.promoter_balance,
+      &accounts.payer.to_account_info(),
+      &accounts.system_program.to_account_info(),
+      quote.promoter_key,
+      payment_token.get_mint(),
+      ctx.bumps.promoter_balance,
+      fill.promoter_amount,
+    )?;
   };
 
-  emit!(events::DepositWithUsdcEvent {
-    epoc: epoc.get_id(),
-    customer: payer.key(),
-    promoter: promoter_key,
-    usdc_amount: amount,
-    asset_amount: asset_amount,
+  emit!(events::DepositWithTokenEvent {
+    epoc: accounts.epoc.get_id(),
+    customer: accounts.payer.key(),
+    promoter: quote.promoter_key,
+    mint: payment_token.get_mint(),
+    amount: received_amount,
+    asset_amount: fill.asset_amount,
   });
 
   Ok(())
 }
 
-pub fn deposit_with_usdt(
-  ctx: Context<DepositUSDT>,
-  promoter_key: Pubkey,
+pub fn get_price(price_update: &Account<PriceUpdateV2>, feed_id: &[u8; 32], max_price_age: u64, max_conf_ratio: u64)
+  -> Result<(u128, u32)>
+{
+  let current_price: Price = price_update
+    .get_price_no_older_than_with_custom_verification_level(&Clock::get()?, max_price_age, feed_id, VerificationLevel::Full)
+    .map_err(|error| match error {
+      GetPriceError::PriceTooOld => errors::Store::PriceIsDown,
+      GetPriceError::MismatchedFeedId => errors::Store::WrongPriceFeedId,
+      GetPriceError::InsufficientVerificationLevel => errors::Store::PriceVerificationTooLow,
+      _ => errors::Store::PriceFeedInvalid,
+    })?;
+
+  if current_price.price <= 0 {
+    return err!(errors::Store::PriceNotPositive);
+  }
+  let price = current_price.price as u128;
+
+  // Confidence interval relative to the price, 10^PRECISION being 100%
+  if u128::from(current_price.conf) * 10u128.pow(PRECISION) > price * u128::from(max_conf_ratio) {
+    return err!(errors::Store::PriceConfidenceTooWide);
+  }
+
+  // Positive exponents are folded into the price so callers always divide by 10^expo
+  if current_price.exponent >= 0 {
+    let price = 10u128.checked_pow(current_price.exponent as u32)
+      .and_then(|scale| price.checked_mul(scale))
+      .ok_or(errors::Store::PriceWrongExponent)?;
+    return Ok((price, 0));
+  }
+
+  let expo = current_price.exponent.unsigned_abs();
+  if expo > 18 {
+    return err!(errors::Store::PriceWrongExponent);
+  }
+
+  Ok((price, expo))
+}
+
+// SOL/USD price with PRECISION decimals, guarded by the reference price and the last accepted price
+fn get_sol_price<'info>(
+  store: &mut Account<'info, Store>,
+  price_update: &Account<'info, PriceUpdateV2>,
+  reference_price: &Option<Account<'info, ReferencePrice>>,
+  max_price_age: u64,
+) -> Result<u64> {
+  let current_timestamp = Clock::get()?.unix_timestamp;
+  let reference_price = reference_price.as_ref()
+    .filter(|reference_price| reference_price.is_fresh(current_timestamp, store.get_reference_max_age()));
+
+  // Only a feed gone stale may fall back to the reference price, any other oracle error is final
+  let oracle_price = match get_price(price_update, &store.get_feed_id(), STALENESS_THRESHOLD, store.get_max_conf_ratio()) {
+    Ok((price, expo)) => Some(to_u64(mul_div(price, 10u128.pow(PRECISION), 10u128.pow(expo))?)?),
+    Err(error) if reference_price.is_some() && error == error!(errors::Store::PriceIsDown) => None,
+    Err(error) => return Err(error),
+  };
+
+  let (price, publish_time) = match (oracle_price, reference_price) {
+    (Some(price), Some(reference_price)) => {
+      if store.is_deviation_exceeded(price, reference_price.get_price()) {
+        return err!(errors::Store::PriceDeviationTooLarge);
+      }
+      (price, price_update.price_message.publish_time)
+    },
+    (Some(price), None) => {
+      if store.has_deviation_check() {
+        return err!(errors::Store::PriceReferenceUnavailable);
+      }
+      (price, price_update.price_message.publish_time)
+    },
+    (None, Some(reference_price)) => (reference_price.get_price(), reference_price.get_updated_ts()),
+    (None, None) => return err!(errors::Store::PriceIsDown),
+  };
+
+  // The buyer may only tighten the staleness threshold, whichever price ends up being used
+  if max_price_age > 0 && current_timestamp.saturating_sub(publish_time) > max_price_age as i64 {
+    return err!(errors::Store::PriceTooOld);
+  }
+
+  if store.is_price_move_exceeded(price, current_timestamp) {
+    return err!(errors::Store::PriceMoveTooLarge);
+  }
+
+  store.set_last_price(price, current_timestamp)?;
+
+  Ok(price)
+}
+
+pub fn transfer_from_escrow<'info>(
+  store: &Account<'info, Store>,
+  escrow_ata: &InterfaceAccount<'info, token_interface::TokenAccount>,
+  to_ata: &InterfaceAccount<'info, token_interface::TokenAccount>,
+  mint: &InterfaceAccount<'info, token_interface::Mint>,
+  asset_program: &Interface<'info, TokenInterface>,
+  bump: u8,
   amount: u64,
-) -> Result<()> {
-  let payer = &mut ctx.accounts.payer;
-  let store = &mut ctx.accounts.store;
-  let epoc = &mut ctx.accounts.epoc;
-  let customer = &mut ctx.accounts.customer;
-  let promoter = &mut ctx.accounts.promoter;
+)
+  -> Result<()>
+{
+  let bump = &[bump];
+  let seeds: &[&[u8]] = &[bump];
+  let signer_seeds = &[seeds];
+
+  let cpi_accounts = TransferChecked {
+    from: escrow_ata.to_account_info(),
+    mint: mint.to_account_info(),
+    to: to_ata.to_account_info(),
+    authority: store.to_account_info(),
+  };
+  let ctx = CpiContext::new_with_signer(asset_program.to_account_info(), cpi_accounts, signer_seeds);
+  token_interface::transfer_checked(ctx, amount, mint.decimals)
+}
 
-  let customer_ata = &ctx.accounts.customer_ata;
-  let treasury_ata = &ctx.accounts.treasury_ata;
-  let promoter_pda_ata = &ctx.accounts.promoter_pda_ata;
-  let asset_program = &ctx.accounts.asset_program;
+pub fn transfer_from_vault<'info>(
+  store: &Account<'info, Store>,
+  vault: &Account<'info, TokenAccount>,
+  to_ata: &Account<'info, TokenAccount>,
+  asset_program: &Program<'info, Token>,
+  bump: u8,
+  amount: u64,
+)
+  -> Result<()>
+{
+  let bump = &[bump];
+  let seeds: &[&[u8]] = &[bump];
+  let signer_seeds = &[seeds];
+
+  let cpi_accounts = SplTransfer {
+    from: vault.to_account_info(),
+    to: to_ata.to_account_info(),
+    authority: store.to_account_info(),
+  };
+  let ctx = CpiContext::new_with_signer(asset_program.to_account_info(), cpi_accounts, signer_seeds);
+  token::transfer(ctx, amount)
+}
+
+// Accounts shared by the SOL and token deposits
+struct Sale<'a, 'info> {
+  store: &'a mut Account<'info, Store>,
+  payer: &'a Signer<'info>,
+  epoc: &'a mut Account<'info, Epoc>,
+  customer: &'a mut Account<'info, Customer>,
+  allocation: &'a mut Account<'info, Allocation>,
+  next_epoc: &'a mut Option<Account<'info, Epoc>>,
+  next_allocation: &'a mut Option<Account<'info, Allocation>>,
+  referral_code: &'a Option<Account<'info, ReferralCode>>,
+  promoter: &'a Option<Account<'info, Promoter>>,
+  promoter_allocation: AccountInfo<'info>,
+  next_promoter_allocation: AccountInfo<'info>,
+  system_program: AccountInfo<'info>,
+  remaining_accounts: &'info [AccountInfo<'info>],
+  promoter_allocation_bump: u8,
+  next_promoter_allocation_bump: u8,
+}
+
+// Promoter and fees of a deposit, with the largest USD amount the epocs can still fill
+struct Quote {
+  promoter_key: Pubkey,
+  first_fee: u64,
+  second_fee: u64,
+  available_usd_amount: u128,
+  current_timestamp: i64,
+}
 
-  if !store.is_enabled() {
-    return err!(errors::Store::StoreNotEnabled);
+// Assets bought on one epoc with their USD cost and the promoter bonus reserved on top of them
+struct Purchase {
+  usd_amount: u128,
+  asset_amount: u128,
+  bonus_amount: u128,
+}
+
+// Outcome of a deposit, the commission and the amount kept by the store being in payment units
+struct Fill {
+  asset_amount: u128,
+  promoter_amount: u64,
+  to_amount: u64,
+}
+
+impl<'a, 'info> Sale<'a, 'info> {
+  fn check(
+    &self,
+    current_timestamp: i64,
+  )
+    -> Result<()>
+  {
+    if !self.store.is_enabled() {
+      return err!(errors::Store::StoreNotEnabled);
+    }
+
+    if !self.epoc.is_enabled(current_timestamp) {
+      return err!(errors::Store::EpocNotEnabled);
+    }
+
+    if !self.epoc.is_within_window(current_timestamp) {
+      return err!(errors::Store::EpocOutsideWindow);
+    }
+
+    if !self.epoc.has_window() && self.store.get_epoc() != self.epoc.get_id() {
+      return err!(errors::Store::InactiveEpoc);
+    }
+
+    Ok(())
   }
 
-  if !epoc.is_enabled() {
-    return err!(errors::Store::EpocNotEnabled);
+  fn quote(
+    &mut self,
+    args: &DepositArgs,
+    current_timestamp: i64,
+  )
+    -> Result<Quote>
+  {
+    // The promoter comes first, its bonus being reserved from the epoc supply along with the customer assets
+    let promoter_key = resolve_promoter(self.store, self.customer, self.payer.key(), args.promoter_key, self.referral_code, self.promoter)?;
+    bind_referrer(self.customer, self.payer.key(), promoter_key)?;
+    let (first_fee, second_fee) = get_fee(self.store, promoter_key, self.promoter);
+    let available_usd_amount = self.get_available_usd_amount(args, second_fee, current_timestamp)?;
+
+    Ok(Quote { promoter_key, first_fee, second_fee, available_usd_amount, current_timestamp })
   }
 
-  if store.get_epoc() != epoc.get_id() {
-    return err!(errors::Store::InactiveEpoc);
+  // Sells the assets paid with `amount`, then credits the promoter bonus and the upline commissions
+  fn fill(
+    &mut self,
+    args: &DepositArgs,
+    quote: &Quote,
+    usd_amount: u128,
+    amount: u64,
+    mint: Option<Pubkey>,
+  )
+    -> Result<Fill>
+  {
+    if self.store.get_max_cap() < usd_amount {
+      return err!(errors::Store::StoreMaxCapExceeded);
+    }
+
+    if self.store.get_min_cap() > usd_amount {
+      return err!(errors::Store::StoreMinCapNotReached);
+    }
+
+    let (asset_amount, bonus_amount, next_bonus_amount) = self.allocate(args, usd_amount, quote.second_fee, quote.current_timestamp)?;
+
+    if asset_amount < args.min_asset_amount {
+      return err!(errors::Store::SlippageExceeded);
+    }
+
+    if self.store.is_wallet_cap_exceeded(add(self.customer.get_usd_amount(), usd_amount)?, add(self.customer.get_asset_amount(), asset_amount)?) {
+      return err!(errors::Store::CustomerAllocationExceeded);
+    }
+
+    // Commissions are rounded down
+    let promoter_amount = to_u64(mul_div(u128::from(amount), u128::from(quote.first_fee), 10u128.pow(PRECISION))?)?;
+    let upline_amount = self.pay_uplines(quote.promoter_key, mint, amount, promoter_amount)?;
+    let to_amount = amount.checked_sub(promoter_amount)
+      .and_then(|to_amount| to_amount.checked_sub(upline_amount))
+      .ok_or(errors::Store::MathOverflow)?;
+
+    // The bonus reserved by `allocate` vests with the epoc it was taken from
+    credit_bonus(
+      self.epoc.get_id(),
+      &self.promoter_allocation,
+      &self.payer.to_account_info(),
+      &self.system_program,
+      quote.promoter_key,
+      self.promoter_allocation_bump,
+      bonus_amount,
+    )?;
+
+    if let Some(next_epoc) = self.next_epoc.as_ref() {
+      credit_bonus(
+        next_epoc.get_id(),
+        &self.next_promoter_allocation,
+        &self.payer.to_account_info(),
+        &self.system_program,
+        quote.promoter_key,
+        self.next_promoter_allocation_bump,
+        next_bonus_amount,
+      )?;
+    }
+
+    // Updating store details
+    self.store.set_total_sold(add(asset_amount, add(bonus_amount, next_bonus_amount)?)?)?;
+    self.store.set_total_raised(usd_amount)?;
+
+    // Updating customer details
+    self.customer.set_asset_amount(asset_amount)?;
+    self.customer.set_usd_amount(usd_amount)?;
+
+    Ok(Fill { asset_amount, promoter_amount, to_amount })
   }
 
-  let usd_amount = u128::from(amount) * 10u128.pow(STABLE_PRECISION);
-  let asset_amount = usd_amount * 10u128.pow (PRECISION) / u128::from(epoc.get_price());
+  fn allocate(
+    &mut self,
+    args: &DepositArgs,
+    usd_amount: u128,
+    bonus_fee: u64,
+    current_timestamp: i64,
+  )
+    -> Result<(u128, u128, u128)>
+  {
+    let customer_key = self.payer.key();
+    let available_amount = get_available_amount(self.epoc.get_total_supply(), self.epoc.get_total_sold(), bonus_fee)?;
+    let purchase = get_purchase(usd_amount, self.epoc.get_price(), bonus_fee)?;
+
+    if purchase.asset_amount <= available_amount {
+      fill_epoc(self.epoc, self.allocation, customer_key, &purchase, args.max_allocation, &args.proof)?;
+      return Ok((purchase.asset_amount, purchase.bonus_amount, 0));
+    }
+
+    // The current epoc sells out, the remainder is bought at the price of the next queued epoc
+    let (next_epoc, next_allocation) = match (self.next_epoc.as_mut(), self.next_allocation.as_mut()) {
+      (Some(next_epoc), Some(next_allocation))
+        if is_rollover_allowed(self.store, self.epoc, next_epoc, customer_key, args, current_timestamp) => (next_epoc, next_allocation),
+      _ => return err!(errors::Store::EpocSupplyExceeded),
+    };
+
+    let (purchase, next_purchase) = split_rollover(usd_amount, available_amount, self.epoc.get_price(), next_epoc.get_price(), bonus_fee)?;
+    fill_epoc(self.epoc, self.allocation, customer_key, &purchase, args.max_allocation, &args.proof)?;
+
+    if !next_epoc.is_enabled(current_timestamp) {
+      next_epoc.set_enable()?;
+    }
 
-  if store.get_max_cap() < usd_amount {
-    return err!(errors::Store::StoreMaxCapExceeded);
+    self.store.advance_epoc()?;
+    fill_epoc(next_epoc, next_allocation, customer_key, &next_purchase, args.next_max_allocation, &args.next_proof)?;
+
+    emit!(events::EpocRolloverEvent {
+      previous_epoc: self.epoc.get_id(),
+      epoc: next_epoc.get_id(),
+    });
+
+    Ok((add(purchase.asset_amount, next_purchase.asset_amount)?, purchase.bonus_amount, next_purchase.bonus_amount))
   }
 
-  if store.get_min_cap() > usd_amount {
-    return err!(errors::Store::StoreMinCapNotReached);
+  // The largest USD amount `allocate` fills, on the current epoc and the next queued one when the rest may roll over
+  fn get_available_usd_amount(
+    &self,
+    args: &DepositArgs,
+    bonus_fee: u64,
+    current_timestamp: i64,
+  )
+    -> Result<u128>
+  {
+    let available_amount = get_available_amount(self.epoc.get_total_supply(), self.epoc.get_total_sold(), bonus_fee)?;
+    let next_available = self.next_epoc.as_ref()
+      .filter(|_| self.next_allocation.is_some())
+      .filter(|next_epoc| is_rollover_allowed(self.store, self.epoc, next_epoc, self.payer.key(), args, current_timestamp))
+      .map(|next_epoc| {
+        get_available_amount(next_epoc.get_total_supply(), next_epoc.get_total_sold(), bonus_fee)
+          .map(|next_available_amount| (next_available_amount, next_epoc.get_price()))
+      })
+      .transpose()?;
+
+    get_available_cost(available_amount, self.epoc.get_price(), next_available)
   }
 
-  if epoc.get_total_sold() + asset_amount > epoc.get_total_supply() {
+  // Credits the promoter's ancestors, passed as remaining accounts (promoter and, for tokens, its balance per level),
+  // keeping the total commission under the store maximum. Returns the amount credited to the uplines.
+  fn pay_uplines(
+    &self,
+    promoter_key: Pubkey,
+    mint: Option<Pubkey>,
+    amount: u64,
+    promoter_amount: u64,
+  )
+    -> Result<u64>
+  {
+    let store = &self.store;
+    let customer_key = self.payer.key();
+    let promoter = match self.promoter {
+      Some(promoter) if Pubkey::from_str(EMPTY_PROMOTER) != Ok(promoter_key) => promoter,
+      _ => return Ok(0),
+    };
+
+    let max_amount = to_u64(mul_div(u128::from(amount), u128::from(store.get_max_total_fee()), 10u128.pow(PRECISION))?)?;
+    let mut paid_amount = promoter_amount;
+    let mut upline_key = promoter.get_upline();
+    let mut accounts = self.remaining_accounts.iter();
+
+    for (level, upline_fee) in store.get_upline_fees().into_iter().enumerate() {
+      // The chain ends at an unset upline or a loop back to the deposit parties, never at a missing account
+      if upline_key == Pubkey::default() || upline_key == promoter_key || upline_key == customer_key {
+        break;
+      }
+
+      let upline_info = accounts.next().ok_or(errors::Store::PromoterWrongUpline)?;
+      let (upline_address, _) = Pubkey::find_program_address(&[PROMOTER_TAG, b"_", upline_key.as_ref()], &crate::ID);
+      if upline_info.key() != upline_address {
+        return err!(errors::Store::PromoterWrongUpline);
+      }
+      // An upline that is not registered or still in the legacy layout ends the chain
+      let mut upline = match Account::<Promoter>::try_from(upline_info) {
+        Ok(upline) if upline.is_initialized() => upline,
+        _ => break,
+      };
+
+      let upline_amount = get_upline_amount(amount, upline_fee, max_amount, paid_amount)?;
+
+      if let Some(mint) = mint {
+        let balance_info = accounts.next().ok_or(errors::Store::PromoterWrongUpline)?;
+        let (balance_address, bump) = Pubkey::find_program_address(&[PROMOTER_TAG, b"_", upline_key.as_ref(), b"_", mint.as_ref()], &crate::ID);
+        if balance_info.key() != balance_address {
+          return err!(errors::Store::PromoterWrongUpline);
+        }
+
+        if upline.is_enabled() {
+          credit_balance(balance_info, &self.payer.to_account_info(), &self.system_program, upline_key, mint, bump, upline_amount)?;
+        }
+      } else if upline.is_enabled() && upline_amount > 0 {
+        upline.set_sol_fee_amount(upline_amount)?;
+        upline.exit(&crate::ID)?;
+      }
+
+      if upline.is_enabled() && upline_amount > 0 {
+        paid_amount = paid_amount.checked_add(upline_amount).ok_or(errors::Store::MathOverflow)?;
+
+        emit!(events::UplineCommissionEvent {
+          customer: customer_key,
+          promoter: upline_key,
+          level: (level + 2) as u8,
+          amount: upline_amount,
+        });
+      }
+
+      upline_key = upline.get_upline();
+    }
+
+    paid_amount.checked_sub(promoter_amount).ok_or(error!(errors::Store::MathOverflow))
+  }
+}
+
+// Assets still sellable on an epoc, leaving room for the promoter bonus paid on top of them
+fn get_available_amount(
+  total_supply: u128,
+  total_sold: u128,
+  bonus_fee: u64,
+)
+  -> Result<u128>
+{
+  let available_amount = sub(total_supply, total_sold)?;
+  mul_div(available_amount, 10u128.pow(PRECISION), add(10u128.pow(PRECISION), u128::from(bonus_fee))?)
+}
+
+// Cost of the available assets, the current epoc's being rounded up when the rest rolls over to the next one
+fn get_available_cost(
+  available_amount: u128,
+  price: u64,
+  next_available: Option<(u128, u64)>,
+)
+  -> Result<u128>
+{
+  match next_available {
+    Some((next_available_amount, next_price)) => add(
+      mul_div_ceil(available_amount, u128::from(price), 10u128.pow(PRECISION))?,
+      mul_div(next_available_amount, u128::from(next_price), 10u128.pow(PRECISION))?,
+    ),
+    None => mul_div(available_amount, u128::from(price), 10u128.pow(PRECISION)),
+  }
+}
+
+// Assets bought on an epoc with their bonus, both rounded down against the buyer
+fn get_purchase(
+  usd_amount: u128,
+  price: u64,
+  bonus_fee: u64,
+)
+  -> Result<Purchase>
+{
+  let asset_amount = mul_div(usd_amount, 10u128.pow(PRECISION), u128::from(price))?;
+  let bonus_amount = mul_div(asset_amount, u128::from(bonus_fee), 10u128.pow(PRECISION))?;
+
+  Ok(Purchase { usd_amount, asset_amount, bonus_amount })
+}
+
+// Splits a purchase selling out the current epoc, whose cost is rounded up, the rest being bought at the next epoc price
+fn split_rollover(
+  usd_amount: u128,
+  available_amount: u128,
+  price: u64,
+  next_price: u64,
+  bonus_fee: u64,
+)
+  -> Result<(Purchase, Purchase)>
+{
+  let current_usd_amount = mul_div_ceil(available_amount, u128::from(price), 10u128.pow(PRECISION))?.min(usd_amount);
+  let bonus_amount = mul_div(available_amount, u128::from(bonus_fee), 10u128.pow(PRECISION))?;
+  let purchase = Purchase { usd_amount: current_usd_amount, asset_amount: available_amount, bonus_amount };
+
+  Ok((purchase, get_purchase(sub(usd_amount, current_usd_amount)?, next_price, bonus_fee)?))
+}
+
+// Share of a payment that fits in the available USD amount, rounded up when the buyer is charged
+// for exactly the available amount and down when the payment is valued once received
+fn get_partial_amount(
+  amount: u64,
+  usd_amount: u128,
+  available_usd_amount: u128,
+  round_up: bool,
+)
+  -> Result<u64>
+{
+  if round_up {
+    to_u64(mul_div_ceil(u128::from(amount), available_usd_amount, usd_amount)?)
+  } else {
+    to_u64(mul_div(u128::from(amount), available_usd_amount, usd_amount)?)
+  }
+}
+
+// Commission of an upline level, capped by what the promoter and the lower levels left of the store maximum
+fn get_upline_amount(
+  amount: u64,
+  upline_fee: u64,
+  max_amount: u64,
+  paid_amount: u64,
+)
+  -> Result<u64>
+{
+  let upline_amount = to_u64(mul_div(u128::from(amount), u128::from(upline_fee), 10u128.pow(PRECISION))?)?;
+  Ok(upline_amount.min(max_amount.saturating_sub(paid_amount)))
+}
+
+// The next queued epoc only takes the remainder once its window is open and the customer is whitelisted for it
+fn is_rollover_allowed(
+  store: &Account<Store>,
+  epoc: &Account<Epoc>,
+  next_epoc: &Account<Epoc>,
+  customer_key: Pubkey,
+  args: &DepositArgs,
+  current_timestamp: i64,
+)
+  -> bool
+{
+  store.get_epoc() == epoc.get_id()
+    && next_epoc.key() != epoc.key()
+    && !next_epoc.is_disabled()
+    && next_epoc.is_within_window(current_timestamp)
+    && next_epoc.is_whitelisted(customer_key, args.next_max_allocation, &args.next_proof)
+}
+
+fn fill_epoc<'info>(
+  epoc: &mut Account<'info, Epoc>,
+  allocation: &mut Account<'info, Allocation>,
+  customer_key: Pubkey,
+  purchase: &Purchase,
+  max_allocation: u128,
+  proof: &[[u8; 32]],
+) -> Result<()> {
+  let Purchase { usd_amount, asset_amount, bonus_amount } = *purchase;
+
+  // The promoter bonus is reserved with the assets, so that it is never clipped once they are sold
+  let sold_amount = add(asset_amount, bonus_amount)?;
+  if add(epoc.get_total_sold(), sold_amount)? > epoc.get_total_supply() {
     return err!(errors::Store::EpocSupplyExceeded);
   }
 
-  let (promoter_stable_fee_amount, promoter_asset_amount) = get_fee(store, promoter_key, promoter, amount, asset_amount).unwrap();
-  let mut to_amount = amount;
-  if promoter_stable_fee_amount > 0 {
-    to_amount = to_amount - promoter_stable_fee_amount;
+  if epoc.is_wallet_cap_exceeded(add(allocation.get_usd_amount(), usd_amount)?, add(allocation.get_asset_amount(), asset_amount)?) {
+    return err!(errors::Store::CustomerAllocationExceeded);
   }
 
-  let cpi_accounts = SplTransfer {
-    from: customer_ata.to_account_info(),
-    to: treasury_ata.to_account_info(),
-    authority: payer.to_account_info(),
-  };
-  let cpi_program = asset_program.to_account_info();
-  token::transfer(CpiContext::new(cpi_program, cpi_accounts), to_amount).unwrap();
-  
-  if promoter_stable_fee_amount > 0 {
-    let cpi_accounts = SplTransfer {
-      from: customer_ata.to_account_info(),
-      to: promoter_pda_ata.to_account_info(),
-      authority: payer.to_account_info(),
+  if !epoc.is_whitelisted(customer_key, max_allocation, proof) {
+    return err!(errors::Store::CustomerNotWhitelisted);
+  }
+
+  if max_allocation > 0 && add(allocation.get_asset_amount(), asset_amount)? > max_allocation {
+    return err!(errors::Store::CustomerAllocationExceeded);
+  }
+
+  epoc.set_total_sold(sold_amount)?;
+  allocation.set_asset_amount(asset_amount)?;
+  allocation.set_usd_amount(usd_amount)
+}
+
+// Credits the promoter bonus reserved on an epoc, vesting with it
+pub fn credit_bonus<'info>(
+  epoc_id: i16,
+  promoter_allocation: &AccountInfo<'info>,
+  payer: &AccountInfo<'info>,
+  system_program: &AccountInfo<'info>,
+  promoter_key: Pubkey,
+  bump: u8,
+  asset_amount: u128,
+)
+  -> Result<()>
+{
+  if asset_amount == 0 {
+    return Ok(());
+  }
+
+  let epoc_id = epoc_id.to_le_bytes();
+  let seeds: &[&[u8]] = &[PROMOTER_TAG, b"_", ALLOCATION_TAG, b"_", &epoc_id, b"_", promoter_key.as_ref(), &[bump]];
+  init_account(promoter_allocation, payer, system_program, seeds, 8 + Allocation::MAX_SIZE, Allocation::DISCRIMINATOR)?;
+
+  let mut allocation = Allocation::try_deserialize(&mut &promoter_allocation.try_borrow_data()?[..])?;
+  allocation.set_asset_amount(asset_amount)?;
+  allocation.try_serialize(&mut &mut promoter_allocation.try_borrow_mut_data()?[..])
+}
+
+// Token commissions are kept per promoter and mint, the balance being created with the first one
+fn credit_balance<'info>(
+  promoter_balance: &AccountInfo<'info>,
+  payer: &AccountInfo<'info>,
+  system_program: &AccountInfo<'info>,
+  promoter_key: Pubkey,
+  mint: Pubkey,
+  bump: u8,
+  amount: u64,
+)
+  -> Result<()>
+{
+  if amount == 0 {
+    return Ok(());
+  }
+
+  let seeds: &[&[u8]] = &[PROMOTER_TAG, b"_", promoter_key.as_ref(), b"_", mint.as_ref(), &[bump]];
+  init_account(promoter_balance, payer, system_program, seeds, 8 + PromoterBalance::MAX_SIZE, PromoterBalance::DISCRIMINATOR)?;
+
+  let mut balance = PromoterBalance::try_deserialize(&mut &promoter_balance.try_borrow_data()?[..])?;
+  balance.set_amount(amount)?;
+  balance.try_serialize(&mut &mut promoter_balance.try_borrow_mut_data()?[..])
+}
+
+// Creates a program account at a PDA unless it already exists, the payer covering the rent.
+// Lamports sent to the address beforehand do not prevent its creation.
+fn init_account<'info>(
+  account_info: &AccountInfo<'info>,
+  payer: &AccountInfo<'info>,
+  system_program: &AccountInfo<'info>,
+  seeds: &[&[u8]],
+  space: usize,
+  discriminator: [u8; 8],
+)
+  -> Result<()>
+{
+  if account_info.owner == &crate::ID {
+    return Ok(());
+  }
+
+  let signer_seeds = &[seeds];
+  let rent = Rent::get()?.minimum_balance(space);
+  let lamports = account_info.lamports();
+
+  if lamports == 0 {
+    let cpi_accounts = CreateAccount {
+      from: payer.clone(),
+      to: account_info.clone(),
     };
-    let cpi_program = asset_program.to_account_info();
-    token::transfer(CpiContext::new(cpi_program, cpi_accounts), promoter_stable_fee_amount).unwrap();
+    system_program::create_account(CpiContext::new_with_signer(system_program.clone(), cpi_accounts, signer_seeds), rent, space as u64, &crate::ID)?;
+  } else {
+    if rent > lamports {
+      let cpi_accounts = SystemTransfer {
+        from: payer.clone(),
+        to: account_info.clone(),
+      };
+      system_program::transfer(CpiContext::new(system_program.clone(), cpi_accounts), rent - lamports)?;
+    }
+
+    let cpi_accounts = Allocate {
+      account_to_allocate: account_info.clone(),
+    };
+    system_program::allocate(CpiContext::new_with_signer(system_program.clone(), cpi_accounts, signer_seeds), space as u64)?;
+
+    let cpi_accounts = Assign {
+      account_to_assign: account_info.clone(),
+    };
+    system_program::assign(CpiContext::new_with_signer(system_program.clone(), cpi_accounts, signer_seeds), &crate::ID)?;
   }
 
-  // Updating store details
-  store.set_total_sold(asset_amount).unwrap();
+  account_info.try_borrow_mut_data()?[..8].copy_from_slice(&discriminator);
 
-  // Updating epoc details
-  epoc.set_total_sold(asset_amount).unwrap();
+  Ok(())
+}
 
-  // Updating customer details
-  customer.set_asset_amount(asset_amount).unwrap();
+// A revoked code fails the deposit instead of silently dropping the promoter
+fn get_code_promoter(
+  referral_code: &Option<Account<ReferralCode>>,
+  promoter_key: Pubkey,
+)
+  -> Result<Pubkey>
+{
+  if referral_code.as_ref().is_some_and(|referral_code| referral_code.is_revoked()) {
+    return err!(errors::Store::ReferralCodeRevoked);
+  }
 
-  // Updating promoter details
-  if Pubkey::from_str(EMPTY_PROMOTER) != Ok(promoter_key){
-    promoter.set_usdt_amount(promoter_stable_fee_amount).unwrap();
-    promoter.set_asset_amount(promoter_asset_amount).unwrap();
-  };
+  Ok(ReferralCode::get_promoter_or(referral_code, promoter_key))
+}
+
+// Disabled promoters and self-referrals either fail the deposit or fall back to no promoter.
+// A bound referrer always falls back, so that its later disabling cannot lock the customer out of the sale.
+fn resolve_promoter(
+  store: &Account<Store>,
+  customer: &Account<Customer>,
+  customer_key: Pubkey,
+  promoter_key: Pubkey,
+  referral_code: &Option<Account<ReferralCode>>,
+  promoter: &Option<Account<Promoter>>,
+)
+  -> Result<Pubkey>
+{
+  // The referral code is only checked when it is the one being used
+  let promoter_key = if customer.has_referrer() { customer.get_referrer() } else { get_code_promoter(referral_code, promoter_key)? };
 
-  emit!(events::DepositWithUsdtEvent {
-    epoc: epoc.get_id(),
-    customer: payer.key(),
+  let empty_promoter = Pubkey::from_str(EMPTY_PROMOTER).unwrap_or_default();
+  if promoter_key == empty_promoter {
+    return Ok(promoter_key);
+  }
+
+  if is_promoter_credited(promoter_key, customer_key, customer.has_referrer(), promoter.as_deref(), store.is_invalid_promoter_rejected())? {
+    return Ok(promoter_key);
+  }
+
+  emit!(events::PromoterIgnoredEvent {
+    customer: customer_key,
     promoter: promoter_key,
-    usdt_amount: amount,
-    asset_amount: asset_amount,
+    self_referral: promoter_key == customer_key,
   });
 
-  Ok(())
+  Ok(empty_promoter)
 }
 
-pub fn get_price(price_info: &AccountInfo)
-  -> Result<(u128, u32)>
+// Whether the promoter is credited, an ignored one failing the deposit when the store rejects them
+fn is_promoter_credited(
+  promoter_key: Pubkey,
+  customer_key: Pubkey,
+  has_referrer: bool,
+  promoter: Option<&Promoter>,
+  reject_invalid: bool,
+)
+  -> Result<bool>
 {
-  let price_feed: PriceFeed = load_price_feed_from_account_info( &price_info ).unwrap();
-  let current_timestamp = Clock::get()?.unix_timestamp;
-  let current_price: Price = price_feed.get_price_no_older_than(current_timestamp, STALENESS_THRESHOLD).unwrap();
+  // A bound referrer was registered when bound, so its account cannot be left out to skip its commission
+  if has_referrer && promoter.is_none() {
+    return err!(errors::Store::PromoterNotRegistered);
+  }
 
-  let price = u64::try_from(current_price.price).unwrap();
-  let expo = u32::try_from(-current_price.expo).unwrap();
-  Ok((u128::from(price), expo))
+  // Only promoters registered beforehand are credited, deposits never create them
+  let promoter = promoter.filter(|promoter| promoter.is_initialized());
+  let self_referral = promoter_key == customer_key;
+  if !self_referral && promoter.is_some_and(|promoter| promoter.is_enabled()) {
+    return Ok(true);
+  }
+
+  if reject_invalid && !has_referrer {
+    return if self_referral {
+      err!(errors::Store::SelfReferral)
+    } else if promoter.is_none() {
+      err!(errors::Store::PromoterNotRegistered)
+    } else {
+      err!(errors::Store::PromoterDisabled)
+    };
+  }
+
+  Ok(false)
 }
 
-pub fn get_fee(
-  store: &mut Account<Store>,
+// The first promoter credited to a customer stays their referrer for the whole sale
+fn bind_referrer(
+  customer: &mut Account<Customer>,
+  customer_key: Pubkey,
   promoter_key: Pubkey,
-  promoter: &mut Account<Promoter>,
-  amount: u64,
-  asset_amount: u128,
 )
-  -> Result<(u64, u128)>
+  -> Result<()>
 {
-  if Pubkey::from_str(EMPTY_PROMOTER) == Ok(promoter_key){
-    return Ok((0, 0));
+  if customer.has_referrer() || Pubkey::from_str(EMPTY_PROMOTER) == Ok(promoter_key) {
+    return Ok(());
+  }
+
+  customer.set_referrer(promoter_key)?;
+
+  emit!(events::SetReferrerEvent {
+    customer: customer_key,
+    referrer: promoter_key,
+  });
+
+  Ok(())
+}
+
+// Commission taken from the payment and bonus paid in assets, both in PRECISION
+fn get_fee(
+  store: &Account<Store>,
+  promoter_key: Pubkey,
+  promoter: &Option<Account<Promoter>>,
+)
+  -> (u64, u64)
+{
+  let promoter = match promoter {
+    Some(promoter) if Pubkey::from_str(EMPTY_PROMOTER) != Ok(promoter_key) => promoter,
+    _ => return (0, 0),
   };
 
   let (store_main_fee, store_secondary_fee) = store.get_fee();
   let (promoter_main_fee, promoter_secondary_fee) = promoter.get_fee();
 
-  let first_fee = u64::max(store_main_fee, promoter_main_fee);
+  let first_fee = u64::max(store_main_fee, promoter_main_fee).min(store.get_max_total_fee());
   let second_fee = u64::max(store_secondary_fee, promoter_secondary_fee);
 
-  let amount = amount * first_fee / 10u64.pow(PRECISION);
-  let asset_amount = asset_amount * u128::from(second_fee) / 10u128.pow(PRECISION);
+  (first_fee, second_fee)
+}
+
+#[derive(Accounts)]
+pub struct InitStore<'info> {
+  #[account(
+    init,
+    payer = payer,
+    space = 8 + Store::MAX_SIZE,
+    seeds = [],
+    bump,
+  )]
+  pub store: Account<'info, Store>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::Operator, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
+  pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(max_cap: u64, min_cap: u64)]
+pub struct SetStoreCap<'info> {
+  #[account(mut)]
+  pub store: Account<'info, Store>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::Operator, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
+}
+
+#[derive(Accounts)]
+#[instruction(soft_cap: u64)]
+pub struct SetStoreSoftCap<'info> {
+  #[account(mut)]
+  pub store: Account<'info, Store>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::Operator, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
+}
+
+#[derive(Accounts)]
+#[instruction(treasury: Pubkey)]
+pub struct SetStoreTreasury<'info> {
+  #[account(mut)]
+  pub store: Account<'info, Store>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::Operator, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
+}
+
+#[derive(Accounts)]
+#[instruction(feed_id: [u8; 32])]
+pub struct SetStorePriceFeed<'info> {
+  #[account(mut)]
+  pub store: Account<'info, Store>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::Operator, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
+}
+
+#[derive(Accounts)]
+#[instruction(wallet_max_usd: u64, wallet_max_asset: u128)]
+pub struct SetStoreWalletCap<'info> {
+  #[account(mut)]
+  pub store: Account<'info, Store>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::Operator, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
+}
+
+#[derive(Accounts)]
+#[instruction(epoc_queue: Vec<i16>)]
+pub struct SetStoreEpocQueue<'info> {
+  #[account(mut)]
+  pub store: Account<'info, Store>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::SaleManager, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
+}
 
-  Ok((amount, asset_amount))
+#[derive(Accounts)]
+#[instruction(max_conf_ratio: u64)]
+pub struct SetStoreMaxConfRatio<'info> {
+  #[account(mut)]
+  pub store: Account<'info, Store>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::Operator, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
+}
+
+#[derive(Accounts)]
+#[instruction(max_deviation_bps: u64, max_price_move_bps: u64, price_move_window: u64, reference_max_age: u64)]
+pub struct SetStoreCircuitBreaker<'info> {
+  #[account(mut)]
+  pub store: Account<'info, Store>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::Operator, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
+}
+
+#[derive(Accounts)]
+#[instruction(reject_invalid_promoter: bool)]
+pub struct SetStorePromoterPolicy<'info> {
+  #[account(mut)]
+  pub store: Account<'info, Store>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::PromoterManager, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
+}
+
+#[derive(Accounts)]
+#[instruction(upline_fees: Vec<u64>, max_total_fee: u64)]
+pub struct SetStoreUplineFees<'info> {
+  #[account(mut)]
+  pub store: Account<'info, Store>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::PromoterManager, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
+}
+
+#[derive(Accounts)]
+#[instruction(first_fee: u64, second_fee: u64)]
+pub struct SetStoreReward<'info> {
+  #[account(mut)]
+  pub store: Account<'info, Store>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::Operator, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
 }
 
 #[derive(Accounts)]
-pub struct InitStore<'info> {
+pub struct SetStoreEnabled<'info> {
+  #[account(mut)]
+  pub store: Account<'info, Store>,
+  #[account(mut)]
+  pub payer: Signer<'info>,
   #[account(
-    init,
-    payer = payer,
-    space = 8 + Store::MAX_SIZE,
-    seeds = [],
+    seeds = [ADMIN_TAG],
     bump,
+    constraint = admin.has_role(Role::Operator, payer.key()) @ errors::Store::Unauthorized,
   )]
+  pub admin: Account<'info, Admin>,
+}
+
+#[derive(Accounts)]
+pub struct SetStoreDisabled<'info> {
+  #[account(mut)]
   pub store: Account<'info, Store>,
   #[account(mut)]
   pub payer: Signer<'info>,
-  pub system_program: Program<'info, System>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::Pauser, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
 }
 
 #[derive(Accounts)]
-#[instruction(max_cap: u64, min_cap: u64)]
-pub struct SetStoreCap<'info> {
+#[instruction(tge_ts: i64)]
+pub struct SetStoreTge<'info> {
   #[account(mut)]
   pub store: Account<'info, Store>,
   #[account(mut)]
   pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::Operator, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
 }
 
 #[derive(Accounts)]
-#[instruction(first_fee: u64, second_fee: u64)]
-pub struct SetStoreReward<'info> {
+pub struct InitVault<'info> {
   #[account(mut)]
   pub store: Account<'info, Store>,
+  pub mint: Account<'info, Mint>,
+  #[account(
+    init,
+    payer = payer,
+    seeds = [VAULT_TAG],
+    bump,
+    token::mint = mint,
+    token::authority = store,
+    token::token_program = asset_program,
+  )]
+  pub vault: Account<'info, TokenAccount>,
   #[account(mut)]
   pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::Operator, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
+  pub asset_program: Program<'info, Token>,
+  pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct SetStoreEnabled<'info> {
-  #[account(mut)]
+#[instruction(amount: u64)]
+pub struct FundVault<'info> {
   pub store: Account<'info, Store>,
+  #[account(
+    mut,
+    constraint = payer_ata.mint == store.get_mint() @ errors::Store::WrongMint,
+    constraint = payer_ata.owner == payer.key(),
+  )]
+  pub payer_ata: Account<'info, TokenAccount>,
+  #[account(
+    mut,
+    seeds = [VAULT_TAG],
+    bump,
+  )]
+  pub vault: Account<'info, TokenAccount>,
   #[account(mut)]
   pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::Operator, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
+  pub asset_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
-pub struct SetStoreDisabled<'info> {
+pub struct ReleaseEscrow<'info> {
+  #[account(mut)]
+  pub store: Account<'info, Store>,
+  /// CHECK : Checked against the treasury stored on the store account
+  #[account(
+    mut,
+    address = store.get_treasury() @ errors::Store::WrongTreasury,
+  )]
+  pub treasury_info : AccountInfo<'info>,
   #[account(mut)]
+  pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::Operator, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseToken<'info> {
+  #[account(
+    seeds = [],
+    bump,
+  )]
   pub store: Account<'info, Store>,
+  #[account(
+    mut,
+    seeds = [
+      PAYMENT_TOKEN_TAG,
+      b"_",
+      payment_token.get_mint().as_ref()
+    ],
+    bump,
+  )]
+  pub payment_token: Account<'info, PaymentToken>,
+  #[account(
+    address = payment_token.get_mint() @ errors::Store::WrongStablecoin,
+  )]
+  pub mint: InterfaceAccount<'info, token_interface::Mint>,
+  #[account(
+    mut,
+    constraint = escrow_ata.mint == payment_token.get_mint() @ errors::Store::WrongEscrow,
+    constraint = escrow_ata.owner == store.key() @ errors::Store::WrongEscrow,
+  )]
+  pub escrow_ata: InterfaceAccount<'info, token_interface::TokenAccount>,
+  #[account(
+    mut,
+    constraint = treasury_ata.mint == payment_token.get_mint() @ errors::Store::WrongStablecoin,
+    constraint = treasury_ata.owner == store.get_treasury() @ errors::Store::WrongTreasury,
+  )]
+  pub treasury_ata: InterfaceAccount<'info, token_interface::TokenAccount>,
+  pub asset_program: Interface<'info, TokenInterface>,
   #[account(mut)]
   pub payer: Signer<'info>,
+  #[account(
+    seeds = [ADMIN_TAG],
+    bump,
+    constraint = admin.has_role(Role::Operator, payer.key()) @ errors::Store::Unauthorized,
+  )]
+  pub admin: Account<'info, Admin>,
+}
+
+// Deposit parameters, the whitelist entries being those of the current and the next queued epoc
+#[derive(Clone, AnchorDeserialize, AnchorSerialize)]
+pub struct DepositArgs {
+  pub promoter_key: Pubkey,
+  pub amount: u64,
+  pub allow_partial: bool,
+  pub min_asset_amount: u128,
+  pub max_allocation: u128,
+  pub proof: Vec<[u8; 32]>,
+  pub next_max_allocation: u128,
+  pub next_proof: Vec<[u8; 32]>,
 }
 
 #[derive(Accounts)]
-#[instruction(promoter_key: Pubkey, amount: u64)]
+#[instruction(args: DepositArgs)]
 pub struct Deposit<'info> {
   #[account(mut)]
   pub store: Account<'info, Store>,
@@ -443,87 +1637,119 @@ pub struct Deposit<'info> {
   #[account(
     init_if_needed,
     payer = payer,
-    space = 8 + Promoter::MAX_SIZE,
+    space = 8 + Allocation::MAX_SIZE,
     seeds = [
-      PROMOTER_TAG,
+      ALLOCATION_TAG,
+      b"_",
+      &epoc.get_id().to_le_bytes(),
       b"_",
-      promoter_key.key().as_ref()
+      payer.key().as_ref()
     ],
     bump
   )]
-  pub promoter: Account<'info, Promoter>,
-  /// CHECK : We will manually check this against the Pubkey of the price feed
-  pub price_info : AccountInfo<'info>,
-  /// CHECK : We will manually check this against the Pubkey of the treasury
-  #[account(mut)]
-  pub treasury_info : AccountInfo<'info>,
-  pub system_program: Program<'info, System>,
-}
-
-#[derive(Accounts)]
-#[instruction(promoter_key: Pubkey, amount: u64)]
-pub struct DepositUSDC<'info> {
-  #[account(mut)]
-  pub store: Account<'info, Store>,
-  #[account(mut)]
-  pub payer: Signer<'info>,
-  #[account(mut)]
-  pub epoc: Account<'info, Epoc>,
+  pub allocation: Account<'info, Allocation>,
+  #[account(
+    mut,
+    seeds = [
+      EPOC_TAG,
+      b"_",
+      &store.get_next_epoc().to_le_bytes()
+    ],
+    bump
+  )]
+  pub next_epoc: Option<Account<'info, Epoc>>,
   #[account(
     init_if_needed,
     payer = payer,
-    space = 8 + Customer::MAX_SIZE,
+    space = 8 + Allocation::MAX_SIZE,
     seeds = [
-      CUSTOMER_TAG,
+      ALLOCATION_TAG,
+      b"_",
+      &store.get_next_epoc().to_le_bytes(),
       b"_",
       payer.key().as_ref()
     ],
     bump
   )]
-  pub customer: Account<'info, Customer>,
+  pub next_allocation: Option<Account<'info, Allocation>>,
+  pub referral_code: Option<Account<'info, ReferralCode>>,
   #[account(
-    init_if_needed,
-    payer = payer,
-    space = 8 + Promoter::MAX_SIZE,
+    mut,
     seeds = [
       PROMOTER_TAG,
       b"_",
-      promoter_key.key().as_ref()
+      customer.get_referrer_or(ReferralCode::get_promoter_or(&referral_code, args.promoter_key)).as_ref()
     ],
     bump
   )]
-  pub promoter: Account<'info, Promoter>,
+  pub promoter: Option<Account<'info, Promoter>>,
+  /// CHECK : Created with the first bonus credited to the promoter for this epoc
   #[account(
     mut,
-    constraint = customer_ata.mint == USDC.parse::<Pubkey>().unwrap(),
-    constraint = customer_ata.owner == payer.key(),
+    seeds = [
+      PROMOTER_TAG,
+      b"_",
+      ALLOCATION_TAG,
+      b"_",
+      &epoc.get_id().to_le_bytes(),
+      b"_",
+      customer.get_referrer_or(ReferralCode::get_promoter_or(&referral_code, args.promoter_key)).as_ref()
+    ],
+    bump
   )]
-  pub customer_ata: Account<'info, TokenAccount>,
+  pub promoter_allocation: UncheckedAccount<'info>,
+  /// CHECK : Created with the first bonus credited to the promoter for the next queued epoc
   #[account(
     mut,
-    constraint = treasury_ata.mint == USDC.parse::<Pubkey>().unwrap(),
-    constraint = treasury_ata.owner == TREASURY.parse::<Pubkey>().unwrap(),
+    seeds = [
+      PROMOTER_TAG,
+      b"_",
+      ALLOCATION_TAG,
+      b"_",
+      &store.get_next_epoc().to_le_bytes(),
+      b"_",
+      customer.get_referrer_or(ReferralCode::get_promoter_or(&referral_code, args.promoter_key)).as_ref()
+    ],
+    bump
   )]
-  pub treasury_ata: Account<'info, TokenAccount>,
+  pub next_promoter_allocation: UncheckedAccount<'info>,
+  pub price_update: Account<'info, PriceUpdateV2>,
   #[account(
-    mut,
-    constraint = promoter_pda_ata.mint == USDC.parse::<Pubkey>().unwrap(),
-    constraint = promoter_pda_ata.owner == promoter.key(),
+    seeds = [REFERENCE_PRICE_TAG],
+    bump,
   )]
-  pub promoter_pda_ata: Account<'info, TokenAccount>,
-  pub asset_program: Program<'info, Token>,
+  pub reference_price: Option<Account<'info, ReferencePrice>>,
   pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-#[instruction(promoter_key: Pubkey, amount: u64)]
-pub struct DepositUSDT<'info> {
-  #[account(mut)]
+#[instruction(args: DepositArgs)]
+pub struct DepositToken<'info> {
+  #[account(
+    mut,
+    seeds = [],
+    bump,
+  )]
   pub store: Account<'info, Store>,
   #[account(mut)]
   pub payer: Signer<'info>,
   #[account(mut)]
   pub epoc: Account<'info, Epoc>,
+  #[account(
+    mut,
+    seeds = [
+      PAYMENT_TOKEN_TAG,
+      b"_",
+      payment_token.get_mint().as_ref()
+    ],
+    bump,
+  )]
+  pub payment_token: Account<'info, PaymentToken>,
+  #[account(
+    address = payment_token.get_mint() @ errors::Store::WrongStablecoin,
+  )]
+  pub mint: InterfaceAccount<'info, token_interface::Mint>,
+  pub price_update: Option<Account<'info, PriceUpdateV2>>,
   #[account(
     init_if_needed,
     payer = payer,
@@ -539,33 +1765,251 @@ pub struct DepositUSDT<'info> {
   #[account(
     init_if_needed,
     payer = payer,
-    space = 8 + Promoter::MAX_SIZE,
+    space = 8 + Allocation::MAX_SIZE,
+    seeds = [
+      ALLOCATION_TAG,
+      b"_",
+      &epoc.get_id().to_le_bytes(),
+      b"_",
+      payer.key().as_ref()
+    ],
+    bump
+  )]
+  pub allocation: Account<'info, Allocation>,
+  #[account(
+    mut,
+    seeds = [
+      EPOC_TAG,
+      b"_",
+      &store.get_next_epoc().to_le_bytes()
+    ],
+    bump
+  )]
+  pub next_epoc: Option<Account<'info, Epoc>>,
+  #[account(
+    init_if_needed,
+    payer = payer,
+    space = 8 + Allocation::MAX_SIZE,
+    seeds = [
+      ALLOCATION_TAG,
+      b"_",
+      &store.get_next_epoc().to_le_bytes(),
+      b"_",
+      payer.key().as_ref()
+    ],
+    bump
+  )]
+  pub next_allocation: Option<Account<'info, Allocation>>,
+  #[account(
+    init_if_needed,
+    payer = payer,
+    space = 8 + Contribution::MAX_SIZE,
+    seeds = [
+      CONTRIBUTION_TAG,
+      b"_",
+      payment_token.get_mint().as_ref(),
+      b"_",
+      payer.key().as_ref()
+    ],
+    bump
+  )]
+  pub contribution: Account<'info, Contribution>,
+  pub referral_code: Option<Account<'info, ReferralCode>>,
+  #[account(
     seeds = [
       PROMOTER_TAG,
       b"_",
-      promoter_key.key().as_ref()
+      customer.get_referrer_or(ReferralCode::get_promoter_or(&referral_code, args.promoter_key)).as_ref()
     ],
     bump
   )]
-  pub promoter: Account<'info, Promoter>,
+  pub promoter: Option<Account<'info, Promoter>>,
+  /// CHECK : Created with the first bonus credited to the promoter for this epoc
   #[account(
     mut,
-    constraint = customer_ata.mint == USDT.parse::<Pubkey>().unwrap(),
-    constraint = customer_ata.owner == payer.key(),
+    seeds = [
+      PROMOTER_TAG,
+      b"_",
+      ALLOCATION_TAG,
+      b"_",
+      &epoc.get_id().to_le_bytes(),
+      b"_",
+      customer.get_referrer_or(ReferralCode::get_promoter_or(&referral_code, args.promoter_key)).as_ref()
+    ],
+    bump
+  )]
+  pub promoter_allocation: UncheckedAccount<'info>,
+  /// CHECK : Created with the first bonus credited to the promoter for the next queued epoc
+  #[account(
+    mut,
+    seeds = [
+      PROMOTER_TAG,
+      b"_",
+      ALLOCATION_TAG,
+      b"_",
+      &store.get_next_epoc().to_le_bytes(),
+      b"_",
+      customer.get_referrer_or(ReferralCode::get_promoter_or(&referral_code, args.promoter_key)).as_ref()
+    ],
+    bump
+  )]
+  pub next_promoter_allocation: UncheckedAccount<'info>,
+  /// CHECK : Created with the first commission credited to the promoter in this token
+  #[account(
+    mut,
+    seeds = [
+      PROMOTER_TAG,
+      b"_",
+      customer.get_referrer_or(ReferralCode::get_promoter_or(&referral_code, args.promoter_key)).as_ref(),
+      b"_",
+      payment_token.get_mint().as_ref()
+    ],
+    bump
   )]
-  pub customer_ata: Account<'info, TokenAccount>,
+  pub promoter_balance: UncheckedAccount<'info>,
   #[account(
     mut,
-    constraint = treasury_ata.mint == USDT.parse::<Pubkey>().unwrap(),
-    constraint = treasury_ata.owner == TREASURY.parse::<Pubkey>().unwrap(),
+    constraint = customer_ata.mint == payment_token.get_mint() @ errors::Store::WrongStablecoin,
+    constraint = customer_ata.owner == payer.key(),
   )]
-  pub treasury_ata: Account<'info, TokenAccount>,
+  pub customer_ata: InterfaceAccount<'info, token_interface::TokenAccount>,
   #[account(
     mut,
-    constraint = promoter_pda_ata.mint == USDT.parse::<Pubkey>().unwrap(),
-    constraint = promoter_pda_ata.owner == promoter.key(),
+    constraint = escrow_ata.mint == payment_token.get_mint() @ errors::Store::WrongEscrow,
+    constraint = escrow_ata.owner == store.key() @ errors::Store::WrongEscrow,
   )]
-  pub promoter_pda_ata: Account<'info, TokenAccount>,
-  pub asset_program: Program<'info, Token>,
+  pub escrow_ata: InterfaceAccount<'info, token_interface::TokenAccount>,
+  pub asset_program: Interface<'info, TokenInterface>,
   pub system_program: Program<'info, System>,
 }
+
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::state::fixtures::zeroed;
+
+  const BONUS_FEE: u64 = 100_000_000;
+
+  #[test]
+  fn available_amount_leaves_room_for_bonus() {
+    let available_amount = get_available_amount(1_000, 0, BONUS_FEE).unwrap();
+    let purchase = get_purchase(available_amount, 1_000_000_000, BONUS_FEE).unwrap();
+    assert_eq!(available_amount, 909);
+    assert!(purchase.asset_amount + purchase.bonus_amount <= 1_000);
+
+    assert_eq!(get_available_amount(1_000, 100, 0).unwrap(), 900);
+    assert_eq!(get_available_amount(1_000, 1_001, 0).unwrap_err(), error!(errors::Store::MathOverflow));
+  }
+
+  #[test]
+  fn purchase_rounds_down() {
+    let purchase = get_purchase(1_000_000_000, 3_000_000_000, BONUS_FEE).unwrap();
+    assert_eq!(purchase.usd_amount, 1_000_000_000);
+    assert_eq!(purchase.asset_amount, 333_333_333);
+    assert_eq!(purchase.bonus_amount, 33_333_333);
+  }
+
+  #[test]
+  fn rollover_charges_the_current_epoc_rounded_up() {
+    let (purchase, next_purchase) = split_rollover(1_000_000_000, 1_000_000_007, 300_000_000, 350_000_000, BONUS_FEE).unwrap();
+    assert_eq!(purchase.usd_amount, 300_000_003);
+    assert_eq!(purchase.asset_amount, 1_000_000_007);
+    assert_eq!(purchase.bonus_amount, 100_000_000);
+
+    assert_eq!(next_purchase.usd_amount, 699_999_997);
+    assert_eq!(next_purchase.asset_amount, 1_999_999_991);
+    assert_eq!(next_purchase.bonus_amount, 199_999_999);
+  }
+
+  #[test]
+  fn rollover_cost_is_capped_by_the_deposit() {
+    let (purchase, next_purchase) = split_rollover(300_000_002, 1_000_000_007, 300_000_000, 350_000_000, BONUS_FEE).unwrap();
+    assert_eq!(purchase.usd_amount, 300_000_002);
+    assert_eq!(next_purchase.usd_amount, 0);
+    assert_eq!(next_purchase.asset_amount, 0);
+  }
+
+  #[test]
+  fn available_cost_fits_the_next_epoc() {
+    assert_eq!(get_available_cost(1_000_000_007, 300_000_000, None).unwrap(), 300_000_002);
+
+    // Rounding the current epoc's cost down would leave the next epoc 2 assets short
+    let usd_amount = get_available_cost(1_000_000_007, 300_000_000, Some((2_000_000_000, 350_000_000))).unwrap();
+    assert_eq!(usd_amount, 1_000_000_003);
+
+    let (_, next_purchase) = split_rollover(usd_amount, 1_000_000_007, 300_000_000, 350_000_000, 0).unwrap();
+    assert_eq!(next_purchase.asset_amount, 2_000_000_000);
+  }
+
+  fn promoter(enabled: bool) -> Promoter {
+    let mut promoter: Promoter = zeroed();
+    promoter.init(0, 0).unwrap();
+    if !enabled {
+      promoter.disable().unwrap();
+    }
+    promoter
+  }
+
+  #[test]
+  fn registered_promoter_is_credited() {
+    let (promoter_key, customer_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    assert!(is_promoter_credited(promoter_key, customer_key, false, Some(&promoter(true)), true).unwrap());
+    assert!(is_promoter_credited(promoter_key, customer_key, true, Some(&promoter(true)), true).unwrap());
+  }
+
+  #[test]
+  fn invalid_promoter_is_ignored_or_rejected() {
+    let (promoter_key, customer_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let unregistered: Promoter = zeroed();
+
+    assert!(!is_promoter_credited(promoter_key, customer_key, false, None, false).unwrap());
+    assert!(!is_promoter_credited(promoter_key, customer_key, false, Some(&promoter(false)), false).unwrap());
+    assert!(!is_promoter_credited(customer_key, customer_key, false, Some(&promoter(true)), false).unwrap());
+
+    assert_eq!(is_promoter_credited(promoter_key, customer_key, false, None, true).unwrap_err(), error!(errors::Store::PromoterNotRegistered));
+    assert_eq!(is_promoter_credited(promoter_key, customer_key, false, Some(&unregistered), true).unwrap_err(), error!(errors::Store::PromoterNotRegistered));
+    assert_eq!(is_promoter_credited(promoter_key, customer_key, false, Some(&promoter(false)), true).unwrap_err(), error!(errors::Store::PromoterDisabled));
+    assert_eq!(is_promoter_credited(customer_key, customer_key, false, Some(&promoter(true)), true).unwrap_err(), error!(errors::Store::SelfReferral));
+  }
+
+  #[test]
+  fn bound_referrer_is_ignored_but_not_skipped() {
+    let (promoter_key, customer_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    // A disabled referrer falls back to no promoter even when invalid promoters are rejected
+    assert!(!is_promoter_credited(promoter_key, customer_key, true, Some(&promoter(false)), true).unwrap());
+    assert_eq!(is_promoter_credited(promoter_key, customer_key, true, None, false).unwrap_err(), error!(errors::Store::PromoterNotRegistered));
+  }
+
+  #[test]
+  fn upline_amount_within_max_fee() {
+    // 10% for the promoter, then 5% and 3% for the uplines under a 15% maximum
+    let max_amount = 150;
+    let mut paid_amount = 100;
+    let mut upline_amounts = Vec::new();
+    for upline_fee in [50_000_000, 30_000_000, 30_000_000] {
+      let upline_amount = get_upline_amount(1_000, upline_fee, max_amount, paid_amount).unwrap();
+      paid_amount += upline_amount;
+      upline_amounts.push(upline_amount);
+    }
+
+    assert_eq!(upline_amounts, [50, 0, 0]);
+    assert_eq!(get_upline_amount(1_000, 30_000_000, max_amount, 140).unwrap(), 10);
+  }
+
+  #[test]
+  fn upline_amount_when_promoter_exceeds_max_fee() {
+    assert_eq!(get_upline_amount(1_000, 50_000_000, 150, 200).unwrap(), 0);
+    assert_eq!(get_upline_amount(1_000, 0, 150, 0).unwrap(), 0);
+    assert_eq!(get_upline_amount(999, 50_000_000, 150, 0).unwrap(), 49);
+  }
+
+  #[test]
+  fn partial_amount_rounding() {
+    assert_eq!(get_partial_amount(10, 3, 2, true).unwrap(), 7);
+    assert_eq!(get_partial_amount(10, 3, 2, false).unwrap(), 6);
+    assert_eq!(get_partial_amount(10, 3, 0, false).unwrap(), 0);
+  }
+}
@@ -1,8 +1,10 @@
 use anchor_lang::prelude::*;
 use instructions::*;
+use state::admin::Role;
 pub mod config;
 pub mod errors;
 pub mod events;
+pub mod math;
 pub mod state;
 pub mod instructions;
 
@@ -12,13 +14,44 @@ declare_id!("54jF2wtHopafEnsVUbCe1c1Krfcm8jfRjcgrcZ4axsUk");
 pub mod store {
   use super::*;
 
+  pub fn init_admin(
+    ctx: Context<InitAdmin>,
+  ) -> Result<()> {
+    instructions::admin::init_admin(ctx)
+  }
+
+  pub fn add_admin(
+    ctx: Context<AddAdmin>,
+    role: Role,
+    key: Pubkey,
+  ) -> Result<()> {
+    instructions::admin::add_admin(ctx, role, key)
+  }
+
+  pub fn remove_admin(
+    ctx: Context<RemoveAdmin>,
+    role: Role,
+    key: Pubkey,
+  ) -> Result<()> {
+    instructions::admin::remove_admin(ctx, role, key)
+  }
+
+  pub fn transfer_ownership(
+    ctx: Context<TransferOwnership>,
+    pending_owner: Pubkey,
+  ) -> Result<()> {
+    instructions::admin::transfer_ownership(ctx, pending_owner)
+  }
+
+  pub fn accept_ownership(
+    ctx: Context<AcceptOwnership>,
+  ) -> Result<()> {
+    instructions::admin::accept_ownership(ctx)
+  }
+
   pub fn init(
     ctx: Context<InitStore>,
   ) -> Result<()> {
-    if !config::only_admin(ctx.accounts.payer.key()) {
-      return err!(errors::Store::Unauthorized);
-    }
-
     instructions::store::init_store(ctx)
   }
 
@@ -27,67 +60,179 @@ pub mod store {
     max_cap: u64,
     min_cap: u64,
   ) -> Result<()> {
-    if !config::only_admin(ctx.accounts.payer.key()) {
-      return err!(errors::Store::Unauthorized);
-    }
-
     instructions::store::set_store_cap(ctx, max_cap, min_cap)
   }
 
+  pub fn set_store_soft_cap(
+    ctx: Context<SetStoreSoftCap>,
+    soft_cap: u64,
+  ) -> Result<()> {
+    instructions::store::set_store_soft_cap(ctx, soft_cap)
+  }
+
+  pub fn set_store_treasury(
+    ctx: Context<SetStoreTreasury>,
+    treasury: Pubkey,
+  ) -> Result<()> {
+    instructions::store::set_store_treasury(ctx, treasury)
+  }
+
+  pub fn set_store_price_feed(
+    ctx: Context<SetStorePriceFeed>,
+    feed_id: [u8; 32],
+  ) -> Result<()> {
+    instructions::store::set_store_price_feed(ctx, feed_id)
+  }
+
+  pub fn set_store_wallet_cap(
+    ctx: Context<SetStoreWalletCap>,
+    wallet_max_usd: u64,
+    wallet_max_asset: u128,
+  ) -> Result<()> {
+    instructions::store::set_store_wallet_cap(ctx, wallet_max_usd, wallet_max_asset)
+  }
+
+  pub fn set_store_max_conf_ratio(
+    ctx: Context<SetStoreMaxConfRatio>,
+    max_conf_ratio: u64,
+  ) -> Result<()> {
+    instructions::store::set_store_max_conf_ratio(ctx, max_conf_ratio)
+  }
+
+  pub fn set_store_circuit_breaker(
+    ctx: Context<SetStoreCircuitBreaker>,
+    max_deviation_bps: u64,
+    max_price_move_bps: u64,
+    price_move_window: u64,
+    reference_max_age: u64,
+  ) -> Result<()> {
+    instructions::store::set_store_circuit_breaker(ctx, max_deviation_bps, max_price_move_bps, price_move_window, reference_max_age)
+  }
+
+  pub fn set_store_epoc_queue(
+    ctx: Context<SetStoreEpocQueue>,
+    epoc_queue: Vec<i16>,
+  ) -> Result<()> {
+    instructions::store::set_store_epoc_queue(ctx, epoc_queue)
+  }
+
+  pub fn set_store_promoter_policy(
+    ctx: Context<SetStorePromoterPolicy>,
+    reject_invalid_promoter: bool,
+  ) -> Result<()> {
+    instructions::store::set_store_promoter_policy(ctx, reject_invalid_promoter)
+  }
+
+  pub fn set_store_upline_fees(
+    ctx: Context<SetStoreUplineFees>,
+    upline_fees: Vec<u64>,
+    max_total_fee: u64,
+  ) -> Result<()> {
+    instructions::store::set_store_upline_fees(ctx, upline_fees, max_total_fee)
+  }
+
   pub fn set_store_promoter_fee(
     ctx: Context<SetStoreReward>,
     first_fee: u64,
     second_fee: u64,
   ) -> Result<()> {
-    if !config::only_admin(ctx.accounts.payer.key()) {
-      return err!(errors::Store::Unauthorized);
-    }
-
     instructions::store::set_store_fee(ctx, first_fee, second_fee)
   }
 
   pub fn enable_store(
     ctx: Context<SetStoreEnabled>,
   ) -> Result<()> {
-    if !config::only_admin(ctx.accounts.payer.key()) {
-      return err!(errors::Store::Unauthorized);
-    }
-
     instructions::store::enable_store(ctx)
   }
 
   pub fn disable_store(
     ctx: Context<SetStoreDisabled>,
   ) -> Result<()> {
-    if !config::only_admin(ctx.accounts.payer.key()) {
-      return err!(errors::Store::Unauthorized);
-    }
-
     instructions::store::disable_store(ctx)
   }
 
-  pub fn deposit_with_sol(
-    ctx: Context<Deposit>,
-    promoter_key: Pubkey,
-    amount: u64,
+  pub fn set_store_tge(
+    ctx: Context<SetStoreTge>,
+    tge_ts: i64,
   ) -> Result<()> {
-    instructions::store::deposit_with_sol(ctx, promoter_key, amount)
+    instructions::store::set_store_tge(ctx, tge_ts)
   }
 
-  pub fn deposit_with_usdc(
-    ctx: Context<DepositUSDC>,
-    promoter_key: Pubkey,
-    amount: u64,
+  pub fn init_vault(
+    ctx: Context<InitVault>,
   ) -> Result<()> {
-    instructions::store::deposit_with_usdc(ctx, promoter_key, amount)
+    instructions::store::init_vault(ctx)
   }
 
-  pub fn deposit_with_usdt(
-    ctx: Context<DepositUSDT>,
-    promoter_key: Pubkey,
+  pub fn fund_vault(
+    ctx: Context<FundVault>,
     amount: u64,
   ) -> Result<()> {
-    instructions::store::deposit_with_usdt(ctx, promoter_key, amount)
+    instructions::store::fund_vault(ctx, amount)
+  }
+
+  pub fn release_escrow(
+    ctx: Context<ReleaseEscrow>,
+  ) -> Result<()> {
+    instructions::store::release_escrow(ctx)
+  }
+
+  pub fn release_token(
+    ctx: Context<ReleaseToken>,
+  ) -> Result<()> {
+    instructions::store::release_token(ctx)
+  }
+
+  pub fn set_reference_price(
+    ctx: Context<SetReferencePrice>,
+    price: u64,
+  ) -> Result<()> {
+    instructions::reference_price::set_reference_price(ctx, price)
+  }
+
+  pub fn init_payment_token(
+    ctx: Context<InitPaymentToken>,
+    feed_id: [u8; 32],
+    price: u64,
+    peg_tolerance_bps: u64,
+  ) -> Result<()> {
+    instructions::payment_token::init_payment_token(ctx, feed_id, price, peg_tolerance_bps)
+  }
+
+  pub fn set_payment_token_price(
+    ctx: Context<SetPaymentTokenPrice>,
+    feed_id: [u8; 32],
+    price: u64,
+    peg_tolerance_bps: u64,
+  ) -> Result<()> {
+    instructions::payment_token::set_payment_token_price(ctx, feed_id, price, peg_tolerance_bps)
+  }
+
+  pub fn enable_payment_token(
+    ctx: Context<SetPaymentTokenEnabled>,
+  ) -> Result<()> {
+    instructions::payment_token::enable_payment_token(ctx)
+  }
+
+  pub fn disable_payment_token(
+    ctx: Context<SetPaymentTokenDisabled>,
+  ) -> Result<()> {
+    instructions::payment_token::disable_payment_token(ctx)
+  }
+
+  pub fn deposit_with_sol<'info>(
+    ctx: Context<'_, '_, 'info, 'info, Deposit<'info>>,
+    args: DepositArgs,
+    max_price_age: u64,
+  ) -> Result<()> {
+    instructions::store::deposit_with_sol(ctx, args, max_price_age)
+  }
+
+  pub fn deposit_with_token<'info>(
+    ctx: Context<'_, '_, 'info, 'info, DepositToken<'info>>,
+    args: DepositArgs,
+  ) -> Result<()> {
+    instructions::store::deposit_with_token(ctx, args)
   }
 
   pub fn init_epoc(
@@ -95,53 +240,59 @@ pub mod store {
     id: i16,
     price: u64,
     total_supply: u128,
+    start_ts: i64,
+    end_ts: i64,
   ) -> Result<()> {
-    if !config::only_admin(ctx.accounts.payer.key()) {
-      return err!(errors::Store::Unauthorized);
-    }
-
-    instructions::epoc::init_epoc(ctx, id, price, total_supply)
+    instructions::epoc::init_epoc(ctx, id, price, total_supply, start_ts, end_ts)
   }
 
   pub fn set_epoc_price(
     ctx: Context<SetEpocPrice>,
     price: u64,
   ) -> Result<()> {
-    if !config::only_admin(ctx.accounts.payer.key()) {
-      return err!(errors::Store::Unauthorized);
-    }
-
     instructions::epoc::set_epoc_price(ctx, price)
   }
 
+  pub fn set_epoc_vesting(
+    ctx: Context<SetEpocVesting>,
+    tge_unlock: u64,
+    cliff: i64,
+    duration: i64,
+  ) -> Result<()> {
+    instructions::epoc::set_epoc_vesting(ctx, tge_unlock, cliff, duration)
+  }
+
+  pub fn set_epoc_wallet_cap(
+    ctx: Context<SetEpocWalletCap>,
+    wallet_max_usd: u64,
+    wallet_max_asset: u128,
+  ) -> Result<()> {
+    instructions::epoc::set_epoc_wallet_cap(ctx, wallet_max_usd, wallet_max_asset)
+  }
+
+  pub fn set_epoc_merkle_root(
+    ctx: Context<SetEpocMerkleRoot>,
+    merkle_root: [u8; 32],
+  ) -> Result<()> {
+    instructions::epoc::set_epoc_merkle_root(ctx, merkle_root)
+  }
+
   pub fn set_epoc_supply(
     ctx: Context<SetEpocSupply>,
     total_supply: u128,
   ) -> Result<()> {
-    if !config::only_admin(ctx.accounts.payer.key()) {
-      return err!(errors::Store::Unauthorized);
-    }
-
     instructions::epoc::set_epoc_supply(ctx, total_supply)
   }
 
   pub fn enable_epoc(
     ctx: Context<SetEpocEnabled>,
   ) -> Result<()> {
-    if !config::only_admin(ctx.accounts.payer.key()) {
-      return err!(errors::Store::Unauthorized);
-    }
-
     instructions::epoc::enable_epoc(ctx)
   }
 
   pub fn disable_epoc(
     ctx: Context<SetEpocDisabled>,
   ) -> Result<()> {
-    if !config::only_admin(ctx.accounts.payer.key()) {
-      return err!(errors::Store::Unauthorized);
-    }
-
     instructions::epoc::disable_epoc(ctx)
   }
 
@@ -151,42 +302,48 @@ pub mod store {
     first_fee: u64,
     second_fee: u64,
   ) -> Result<()> {
-    if !config::only_admin(ctx.accounts.payer.key()) {
-      return err!(errors::Store::Unauthorized);
-    }
-
     instructions::promoter::init_promoter(ctx, first_fee, second_fee)
   }
 
+  pub fn register_promoter(
+    ctx: Context<RegisterPromoter>,
+    code: String,
+  ) -> Result<()> {
+    instructions::promoter::register_promoter(ctx, code)
+  }
+
+  pub fn revoke_referral_code(
+    ctx: Context<RevokeReferralCode>,
+    code: String,
+  ) -> Result<()> {
+    instructions::promoter::revoke_referral_code(ctx, code)
+  }
+
   pub fn set_promoter_fee(
     ctx: Context<SetPromoterReward>,
     first_fee: u64,
     second_fee: u64,
   ) -> Result<()> {
-    if !config::only_admin(ctx.accounts.payer.key()) {
-      return err!(errors::Store::Unauthorized);
-    }
-
     instructions::promoter::set_promoter_fee(ctx, first_fee, second_fee)
   }
 
+  pub fn set_promoter_upline(
+    ctx: Context<SetPromoterUpline>,
+    promoter_key: Pubkey,
+    upline: Pubkey,
+  ) -> Result<()> {
+    instructions::promoter::set_promoter_upline(ctx, promoter_key, upline)
+  }
+
   pub fn enable_promoter(
     ctx: Context<SetPromoterEnabled>,
   ) -> Result<()> {
-    if !config::only_admin(ctx.accounts.payer.key()) {
-      return err!(errors::Store::Unauthorized);
-    }
-
     instructions::promoter::enable_promoter(ctx)
   }
 
   pub fn disable_promoter(
     ctx: Context<SetPromoterDisabled>,
   ) -> Result<()> {
-    if !config::only_admin(ctx.accounts.payer.key()) {
-      return err!(errors::Store::Unauthorized);
-    }
-
     instructions::promoter::disable_promoter(ctx)
   }
 
@@ -196,15 +353,74 @@ pub mod store {
     instructions::promoter::withdraw_sol(ctx)
   }
 
-  pub fn withdraw_usdc(
-    ctx: Context<WithdrawUSDC>,
+  pub fn withdraw_token(
+    ctx: Context<WithdrawToken>,
+  ) -> Result<()> {
+    instructions::promoter::withdraw_token(ctx)
+  }
+
+  pub fn withdraw_legacy_token(
+    ctx: Context<WithdrawLegacyToken>,
+  ) -> Result<()> {
+    instructions::promoter::withdraw_legacy_token(ctx)
+  }
+
+  pub fn migrate_store(
+    ctx: Context<MigrateStore>,
+  ) -> Result<()> {
+    instructions::migration::migrate_store(ctx)
+  }
+
+  pub fn migrate_epoc(
+    ctx: Context<MigrateEpoc>,
+    id: i16,
+  ) -> Result<()> {
+    instructions::migration::migrate_epoc(ctx, id)
+  }
+
+  pub fn migrate_customer(
+    ctx: Context<MigrateCustomer>,
+    customer_key: Pubkey,
+  ) -> Result<()> {
+    instructions::migration::migrate_customer(ctx, customer_key)
+  }
+
+  pub fn migrate_promoter(
+    ctx: Context<MigratePromoter>,
+    promoter_key: Pubkey,
+  ) -> Result<()> {
+    instructions::migration::migrate_promoter(ctx, promoter_key)
+  }
+
+  pub fn claim_bonus(
+    ctx: Context<ClaimBonus>,
+  ) -> Result<()> {
+    instructions::promoter::claim_bonus(ctx)
+  }
+
+  pub fn set_customer_referrer(
+    ctx: Context<SetCustomerReferrer>,
+    customer_key: Pubkey,
+    referrer: Pubkey,
+  ) -> Result<()> {
+    instructions::customer::set_customer_referrer(ctx, customer_key, referrer)
+  }
+
+  pub fn claim(
+    ctx: Context<Claim>,
+  ) -> Result<()> {
+    instructions::customer::claim(ctx)
+  }
+
+  pub fn refund(
+    ctx: Context<Refund>,
   ) -> Result<()> {
-    instructions::promoter::withdraw_usdc(ctx)
+    instructions::customer::refund(ctx)
   }
 
-  pub fn withdraw_usdt(
-    ctx: Context<WithdrawUSDT>,
+  pub fn refund_token(
+    ctx: Context<RefundToken>,
   ) -> Result<()> {
-    instructions::promoter::withdraw_usdt(ctx)
+    instructions::customer::refund_token(ctx)
   }
 }
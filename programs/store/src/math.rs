@@ -0,0 +1,97 @@
+use anchor_lang::prelude::*;
+use crate::errors;
+
+// value * numerator / denominator, rounded down
+pub fn mul_div(
+  value: u128,
+  numerator: u128,
+  denominator: u128,
+) -> Result<u128> {
+  value.checked_mul(numerator)
+    .and_then(|product| product.checked_div(denominator))
+    .ok_or(error!(errors::Store::MathOverflow))
+}
+
+// value * numerator / denominator, rounded up
+pub fn mul_div_ceil(
+  value: u128,
+  numerator: u128,
+  denominator: u128,
+) -> Result<u128> {
+  if denominator == 0 {
+    return err!(errors::Store::MathOverflow);
+  }
+
+  value.checked_mul(numerator)
+    .map(|product| product.div_ceil(denominator))
+    .ok_or(error!(errors::Store::MathOverflow))
+}
+
+pub fn add(
+  a: u128,
+  b: u128,
+) -> Result<u128> {
+  a.checked_add(b).ok_or(error!(errors::Store::MathOverflow))
+}
+
+pub fn sub(
+  a: u128,
+  b: u128,
+) -> Result<u128> {
+  a.checked_sub(b).ok_or(error!(errors::Store::MathOverflow))
+}
+
+pub fn to_u64(
+  value: u128,
+) -> Result<u64> {
+  u64::try_from(value).map_err(|_| error!(errors::Store::MathOverflow))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn mul_div_rounds_down() {
+    assert_eq!(mul_div(10, 1, 3).unwrap(), 3);
+    assert_eq!(mul_div(2, 5, 3).unwrap(), 3);
+    assert_eq!(mul_div(9, 1, 3).unwrap(), 3);
+    assert_eq!(mul_div(0, 7, 3).unwrap(), 0);
+  }
+
+  #[test]
+  fn mul_div_ceil_rounds_up() {
+    assert_eq!(mul_div_ceil(10, 1, 3).unwrap(), 4);
+    assert_eq!(mul_div_ceil(2, 5, 3).unwrap(), 4);
+    assert_eq!(mul_div_ceil(9, 1, 3).unwrap(), 3);
+    assert_eq!(mul_div_ceil(0, 7, 3).unwrap(), 0);
+  }
+
+  #[test]
+  fn mul_div_overflow() {
+    assert_eq!(mul_div(u128::MAX, 2, 1).unwrap_err(), error!(errors::Store::MathOverflow));
+    assert_eq!(mul_div_ceil(u128::MAX, 2, 1).unwrap_err(), error!(errors::Store::MathOverflow));
+    assert_eq!(mul_div(u128::MAX, 1, 1).unwrap(), u128::MAX);
+    assert_eq!(mul_div_ceil(u128::MAX, 1, 1).unwrap(), u128::MAX);
+  }
+
+  #[test]
+  fn mul_div_zero_denominator() {
+    assert_eq!(mul_div(1, 1, 0).unwrap_err(), error!(errors::Store::MathOverflow));
+    assert_eq!(mul_div_ceil(1, 1, 0).unwrap_err(), error!(errors::Store::MathOverflow));
+  }
+
+  #[test]
+  fn add_sub_overflow() {
+    assert_eq!(add(u128::MAX - 1, 1).unwrap(), u128::MAX);
+    assert_eq!(add(u128::MAX, 1).unwrap_err(), error!(errors::Store::MathOverflow));
+    assert_eq!(sub(1, 1).unwrap(), 0);
+    assert_eq!(sub(0, 1).unwrap_err(), error!(errors::Store::MathOverflow));
+  }
+
+  #[test]
+  fn to_u64_overflow() {
+    assert_eq!(to_u64(u128::from(u64::MAX)).unwrap(), u64::MAX);
+    assert_eq!(to_u64(u128::from(u64::MAX) + 1).unwrap_err(), error!(errors::Store::MathOverflow));
+  }
+}
\ No newline at end of file
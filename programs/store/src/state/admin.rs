@@ -0,0 +1,138 @@
+use anchor_lang::prelude::*;
+use crate::errors;
+
+#[derive(Clone, PartialEq, AnchorDeserialize, AnchorSerialize)]
+pub enum Role {
+  Operator,
+  Pauser,
+  PromoterManager,
+  SaleManager,
+}
+
+#[account]
+pub struct Admin {
+  owner: Pubkey,
+  pending_owner: Pubkey,
+  operators: Vec<Pubkey>,
+  pausers: Vec<Pubkey>,
+  promoter_managers: Vec<Pubkey>,
+  sale_managers: Vec<Pubkey>,
+}
+
+impl Admin {
+  pub const MAX_MEMBERS: usize = 10;
+  pub const MAX_SIZE: usize = (2 * 32) + 4 * (4 + 32 * Admin::MAX_MEMBERS);
+
+  pub fn init(
+    &mut self,
+    owner: Pubkey,
+  ) -> Result<()> {
+    self.owner = owner;
+    self.pending_owner = Pubkey::default();
+    self.operators = Vec::new();
+    self.pausers = Vec::new();
+    self.promoter_managers = Vec::new();
+    self.sale_managers = Vec::new();
+
+    Ok(())
+  }
+
+  pub fn add(
+    &mut self,
+    role: Role,
+    key: Pubkey,
+  ) -> Result<()> {
+    let members = self.get_members(role);
+    if members.contains(&key) {
+      return err!(errors::Store::AdminExists);
+    }
+
+    if members.len() >= Admin::MAX_MEMBERS {
+      return err!(errors::Store::AdminListFull);
+    }
+
+    members.push(key);
+
+    Ok(())
+  }
+
+  pub fn remove(
+    &mut self,
+    role: Role,
+    key: Pubkey,
+  ) -> Result<()> {
+    let members = self.get_members(role);
+    if !members.contains(&key) {
+      return err!(errors::Store::AdminNotFound);
+    }
+
+    members.retain(|member| *member != key);
+
+    Ok(())
+  }
+
+  pub fn set_pending_owner(
+    &mut self,
+    pending_owner: Pubkey,
+  ) -> Result<()> {
+    self.pending_owner = pending_owner;
+
+    Ok(())
+  }
+
+  pub fn accept_owner(
+    &mut self,
+    owner: Pubkey,
+  ) -> Result<()> {
+    if self.pending_owner == Pubkey::default() || self.pending_owner != owner {
+      return err!(errors::Store::WrongPendingOwner);
+    }
+
+    self.owner = owner;
+    self.pending_owner = Pubkey::default();
+
+    Ok(())
+  }
+
+  fn get_members(
+    &mut self,
+    role: Role,
+  ) -> &mut Vec<Pubkey> {
+    match role {
+      Role::Operator => &mut self.operators,
+      Role::Pauser => &mut self.pausers,
+      Role::PromoterManager => &mut self.promoter_managers,
+      Role::SaleManager => &mut self.sale_managers,
+    }
+  }
+
+  pub fn get_owner(
+    &self,
+  ) -> Pubkey {
+    self.owner
+  }
+
+  pub fn is_owner(
+    &self,
+    key: Pubkey,
+  ) -> bool {
+    self.owner == key
+  }
+
+  pub fn has_role(
+    &self,
+    role: Role,
+    key: Pubkey,
+  ) -> bool {
+    if self.is_owner(key) {
+      return true;
+    }
+
+    match role {
+      Role::Operator => self.operators.contains(&key),
+      Role::Pauser => self.pausers.contains(&key),
+      Role::PromoterManager => self.promoter_managers.contains(&key),
+      Role::SaleManager => self.sale_managers.contains(&key),
+    }
+  }
+}
\ No newline at end of file
@@ -0,0 +1,122 @@
+use anchor_lang::prelude::*;
+use crate::errors;
+use crate::config::PRECISION;
+use crate::math::{ mul_div, sub, to_u64 };
+use crate::state::epoc::Epoc;
+
+#[account]
+pub struct Allocation {
+  asset_amount: u128,
+  released_amount: u128,
+  usd_amount: u128,
+}
+
+impl Allocation {
+  pub const MAX_SIZE: usize = 3 * 16;
+
+  pub fn set_asset_amount(
+    &mut self,
+    asset_amount: u128,
+  ) -> Result<()> {
+    self.asset_amount = self.asset_amount.checked_add(asset_amount).ok_or(errors::Store::MathOverflow)?;
+
+    Ok(())
+  }
+
+  pub fn set_released_amount(
+    &mut self,
+    released_amount: u128,
+  ) -> Result<()> {
+    self.released_amount = self.released_amount.checked_add(released_amount).ok_or(errors::Store::MathOverflow)?;
+
+    Ok(())
+  }
+
+  // Releases what vested since the last claim, in mint units rounded down.
+  // Only what is transferred counts as released, the dust rounded off stays claimable.
+  pub fn release(
+    &mut self,
+    epoc: &Epoc,
+    tge_ts: i64,
+    current_ts: i64,
+    decimals: u8,
+  ) -> Result<(u128, u64)> {
+    let vested_amount = epoc.get_vested_amount(self.asset_amount, tge_ts, current_ts)?;
+    let asset_amount = sub(vested_amount, self.released_amount)?;
+    let amount = to_u64(mul_div(asset_amount, 10u128.pow(u32::from(decimals)), 10u128.pow(PRECISION))?)?;
+    let asset_amount = mul_div(u128::from(amount), 10u128.pow(PRECISION), 10u128.pow(u32::from(decimals)))?;
+
+    self.set_released_amount(asset_amount)?;
+
+    Ok((asset_amount, amount))
+  }
+
+  pub fn set_usd_amount(
+    &mut self,
+    usd_amount: u128,
+  ) -> Result<()> {
+    self.usd_amount = self.usd_amount.checked_add(usd_amount).ok_or(errors::Store::MathOverflow)?;
+
+    Ok(())
+  }
+
+  pub fn get_asset_amount(
+    &self,
+  ) -> u128 {
+    self.asset_amount
+  }
+
+  pub fn get_released_amount(
+    &self,
+  ) -> u128 {
+    self.released_amount
+  }
+
+  pub fn get_usd_amount(
+    &self,
+  ) -> u128 {
+    self.usd_amount
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::state::fixtures::zeroed;
+  use crate::state::epoc::tests::epoc as vesting;
+
+  const TGE: i64 = 1_000;
+
+  // Everything unlocks at TGE
+  fn epoc() -> Epoc {
+    vesting(1_000_000_000, 0, 0)
+  }
+
+  #[test]
+  fn nothing_released_before_tge() {
+    let mut allocation: Allocation = zeroed();
+    allocation.set_asset_amount(1_000_000_000).unwrap();
+    assert_eq!(allocation.release(&epoc(), TGE, TGE - 1, 6).unwrap(), (0, 0));
+    assert_eq!(allocation.get_released_amount(), 0);
+  }
+
+  #[test]
+  fn dust_stays_claimable() {
+    let mut allocation: Allocation = zeroed();
+    allocation.set_asset_amount(1_234_567_891).unwrap();
+    assert_eq!(allocation.release(&epoc(), TGE, TGE, 6).unwrap(), (1_234_567_000, 1_234_567));
+    assert_eq!(allocation.release(&epoc(), TGE, TGE, 6).unwrap(), (0, 0));
+
+    allocation.set_asset_amount(109).unwrap();
+    assert_eq!(allocation.release(&epoc(), TGE, TGE, 6).unwrap(), (1_000, 1));
+    assert_eq!(allocation.get_released_amount(), 1_234_568_000);
+  }
+
+  #[test]
+  fn release_without_rounding() {
+    let mut allocation: Allocation = zeroed();
+    allocation.set_asset_amount(1_234_567_891).unwrap();
+    assert_eq!(allocation.release(&epoc(), TGE, TGE, 9).unwrap(), (1_234_567_891, 1_234_567_891));
+    assert_eq!(allocation.release(&epoc(), TGE, TGE, 12).unwrap(), (0, 0));
+  }
+}
\ No newline at end of file
@@ -0,0 +1,34 @@
+use anchor_lang::prelude::*;
+use crate::errors;
+
+#[account]
+pub struct Contribution {
+  amount: u64,
+}
+
+impl Contribution {
+  pub const MAX_SIZE: usize = 8;
+
+  pub fn set_amount(
+    &mut self,
+    amount: u64,
+  ) -> Result<()> {
+    self.amount = self.amount.checked_add(amount).ok_or(errors::Store::MathOverflow)?;
+
+    Ok(())
+  }
+
+  pub fn reset_amount(
+    &mut self,
+  ) -> Result<()> {
+    self.amount = 0;
+
+    Ok(())
+  }
+
+  pub fn get_amount(
+    &self,
+  ) -> u64 {
+    self.amount
+  }
+}
\ No newline at end of file
@@ -1,17 +1,30 @@
 use anchor_lang::prelude::*;
+use crate::errors;
 
 #[account]
 pub struct Customer {
   asset_amount: u128,
+  claimed_amount: u128,
+  usd_amount: u128,
+
+  sol_amount: u64,
+
+  referrer: Pubkey,
 }
 
 impl Customer {
-  pub const MAX_SIZE: usize = 16;
+  pub const MAX_SIZE: usize = (3 * 16) + 8 + 32;
 
   pub fn init(
     &mut self,
   ) -> Result<()> {
     self.asset_amount = 0;
+    self.claimed_amount = 0;
+    self.usd_amount = 0;
+
+    self.sol_amount = 0;
+
+    self.referrer = Pubkey::default();
 
     Ok(())
   }
@@ -20,7 +33,51 @@ impl Customer {
     &mut self,
     asset_amount: u128,
   ) -> Result<()> {
-    self.asset_amount += asset_amount;
+    self.asset_amount = self.asset_amount.checked_add(asset_amount).ok_or(errors::Store::MathOverflow)?;
+
+    Ok(())
+  }
+
+  pub fn set_claimed_amount(
+    &mut self,
+    claimed_amount: u128,
+  ) -> Result<()> {
+    self.claimed_amount = self.claimed_amount.checked_add(claimed_amount).ok_or(errors::Store::MathOverflow)?;
+
+    Ok(())
+  }
+
+  pub fn set_usd_amount(
+    &mut self,
+    usd_amount: u128,
+  ) -> Result<()> {
+    self.usd_amount = self.usd_amount.checked_add(usd_amount).ok_or(errors::Store::MathOverflow)?;
+
+    Ok(())
+  }
+
+  pub fn set_referrer(
+    &mut self,
+    referrer: Pubkey,
+  ) -> Result<()> {
+    self.referrer = referrer;
+
+    Ok(())
+  }
+
+  pub fn set_sol_amount(
+    &mut self,
+    sol_amount: u64,
+  ) -> Result<()> {
+    self.sol_amount = self.sol_amount.checked_add(sol_amount).ok_or(errors::Store::MathOverflow)?;
+
+    Ok(())
+  }
+
+  pub fn reset_sol_amount(
+    &mut self,
+  ) -> Result<()> {
+    self.sol_amount = 0;
 
     Ok(())
   }
@@ -30,4 +87,42 @@ impl Customer {
   ) -> u128 {
     self.asset_amount
   }
+
+  pub fn get_claimed_amount(
+    &mut self,
+  ) -> u128 {
+    self.claimed_amount
+  }
+
+  pub fn get_usd_amount(
+    &mut self,
+  ) -> u128 {
+    self.usd_amount
+  }
+
+  pub fn get_sol_amount(
+    &mut self,
+  ) -> u64 {
+    self.sol_amount
+  }
+
+  pub fn get_referrer(
+    &self,
+  ) -> Pubkey {
+    self.referrer
+  }
+
+  // The bound referrer always wins over the promoter passed to a deposit
+  pub fn get_referrer_or(
+    &self,
+    promoter_key: Pubkey,
+  ) -> Pubkey {
+    if self.has_referrer() { self.referrer } else { promoter_key }
+  }
+
+  pub fn has_referrer(
+    &self,
+  ) -> bool {
+    self.referrer != Pubkey::default()
+  }
 }
\ No newline at end of file
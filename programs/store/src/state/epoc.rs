@@ -1,5 +1,8 @@
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::keccak;
 use crate::errors;
+use crate::config::PRECISION;
+use crate::math::{ add, mul_div, sub };
 
 #[derive(Clone, PartialEq, AnchorDeserialize, AnchorSerialize)]
 pub enum Status {
@@ -15,22 +18,52 @@ pub struct Epoc {
   total_sold: u128,
   total_supply: u128,
   status: Status,
+  tge_unlock: u64,
+  cliff: i64,
+  duration: i64,
+  wallet_max_usd: u64,
+  wallet_max_asset: u128,
+  merkle_root: [u8; 32],
+  start_ts: i64,
+  end_ts: i64,
 }
 
 impl Epoc {
-  pub const MAX_SIZE: usize = 2 + 8 + (2 * 16) + (32 + 1);
+  pub const MAX_SIZE: usize = 2 + 8 + (2 * 16) + (32 + 1) + (3 * 8) + 8 + 16 + 32 + (2 * 8);
 
   pub fn init(
     &mut self,
     id: i16,
     price: u64,
     total_supply: u128,
+    start_ts: i64,
+    end_ts: i64,
   ) -> Result<()> {
+    if start_ts < 0 || end_ts < 0 || (end_ts > 0 && end_ts <= start_ts) {
+      return err!(errors::Store::EpocWrongWindow);
+    }
+
     self.id = id;
     self.price = price;
     self.total_supply = total_supply;
     self.total_sold = 0;
     self.status = Status::None;
+    self.tge_unlock = 10u64.pow(PRECISION);
+    self.cliff = 0;
+    self.duration = 0;
+    self.wallet_max_usd = 0;
+    self.wallet_max_asset = 0;
+    self.merkle_root = [0; 32];
+    self.start_ts = start_ts;
+    self.end_ts = end_ts;
+
+    Ok(())
+  }
+
+  pub fn migrate(
+    &mut self,
+  ) -> Result<()> {
+    self.tge_unlock = 10u64.pow(PRECISION);
 
     Ok(())
   }
@@ -39,7 +72,7 @@ impl Epoc {
     &mut self,
     price: u64,
   ) -> Result<()> {
-    if self.status == Status::Enabled || self.status == Status::Disabled {
+    if self.is_started()? {
       return err!(errors::Store::EpocEnabled);
     }
 
@@ -48,6 +81,51 @@ impl Epoc {
     Ok(())
   }
 
+  pub fn set_vesting(
+    &mut self,
+    tge_unlock: u64,
+    cliff: i64,
+    duration: i64,
+  ) -> Result<()> {
+    if self.is_started()? {
+      return err!(errors::Store::EpocEnabled);
+    }
+
+    if tge_unlock > 10u64.pow(PRECISION) {
+      return err!(errors::Store::EpocTgeUnlockTooLarge);
+    }
+
+    if cliff < 0 || duration < 0 {
+      return err!(errors::Store::EpocWrongVesting);
+    }
+
+    self.tge_unlock = tge_unlock;
+    self.cliff = cliff;
+    self.duration = duration;
+
+    Ok(())
+  }
+
+  pub fn set_wallet_cap(
+    &mut self,
+    wallet_max_usd: u64,
+    wallet_max_asset: u128,
+  ) -> Result<()> {
+    self.wallet_max_usd = wallet_max_usd;
+    self.wallet_max_asset = wallet_max_asset;
+
+    Ok(())
+  }
+
+  pub fn set_merkle_root(
+    &mut self,
+    merkle_root: [u8; 32],
+  ) -> Result<()> {
+    self.merkle_root = merkle_root;
+
+    Ok(())
+  }
+
   pub fn set_total_supply(
     &mut self,
     total_supply: u128,
@@ -76,7 +154,7 @@ impl Epoc {
   pub fn set_disable(
     &mut self,
   ) -> Result<()> {
-    if self.status != Status::Enabled {
+    if self.status == Status::Disabled {
       return err!(errors::Store::EpocDisabled);
     }
 
@@ -89,38 +167,194 @@ impl Epoc {
     &mut self,
     total_sold: u128,
   ) -> Result<()> {
-    self.total_sold += total_sold;
+    self.total_sold = self.total_sold.checked_add(total_sold).ok_or(errors::Store::MathOverflow)?;
 
     Ok(())
   }
 
   pub fn get_id(
-    &mut self,
+    &self,
   ) -> i16 {
     self.id
   }
 
   pub fn get_price(
-    &mut self,
+    &self,
   ) -> u64 {
     self.price
   }
 
   pub fn get_total_sold(
-    &mut self,
+    &self,
   ) -> u128 {
     self.total_sold
   }
 
   pub fn get_total_supply(
-    &mut self,
+    &self,
   ) -> u128 {
     self.total_supply
   }
 
+  pub fn get_vested_amount(
+    &self,
+    asset_amount: u128,
+    tge_ts: i64,
+    current_ts: i64,
+  ) -> Result<u128> {
+    if tge_ts == 0 || current_ts < tge_ts {
+      return Ok(0);
+    }
+
+    let unlocked = mul_div(asset_amount, u128::from(self.tge_unlock), 10u128.pow(PRECISION))?;
+    let elapsed = current_ts.checked_sub(tge_ts).ok_or(errors::Store::MathOverflow)?;
+    if elapsed < self.cliff {
+      return Ok(unlocked);
+    }
+
+    let vesting = elapsed.checked_sub(self.cliff).ok_or(errors::Store::MathOverflow)?;
+    if vesting >= self.duration {
+      return Ok(asset_amount);
+    }
+
+    let locked = sub(asset_amount, unlocked)?;
+    add(unlocked, mul_div(locked, vesting as u128, self.duration as u128)?)
+  }
+
+  pub fn is_wallet_cap_exceeded(
+    &self,
+    usd_amount: u128,
+    asset_amount: u128,
+  ) -> bool {
+    (self.wallet_max_usd > 0 && usd_amount > u128::from(self.wallet_max_usd))
+      || (self.wallet_max_asset > 0 && asset_amount > self.wallet_max_asset)
+  }
+
+  pub fn is_whitelisted(
+    &self,
+    key: Pubkey,
+    max_allocation: u128,
+    proof: &[[u8; 32]],
+  ) -> bool {
+    if self.merkle_root == [0; 32] {
+      return true;
+    }
+
+    let mut node = keccak::hashv(&[key.as_ref(), &max_allocation.to_le_bytes()]).0;
+
+    for sibling in proof.iter() {
+      node = if node <= *sibling {
+        keccak::hashv(&[&node, sibling]).0
+      } else {
+        keccak::hashv(&[sibling, &node]).0
+      };
+    }
+
+    node == self.merkle_root
+  }
+
+  pub fn has_window(
+    &self,
+  ) -> bool {
+    self.start_ts > 0
+  }
+
+  pub fn is_within_window(
+    &self,
+    current_ts: i64,
+  ) -> bool {
+    current_ts >= self.start_ts && (self.end_ts == 0 || current_ts < self.end_ts)
+  }
+
   pub fn is_enabled(
     &self,
+    current_ts: i64,
   ) -> bool {
-    self.status == Status::Enabled
+    match self.status {
+      Status::Enabled => true,
+      Status::Disabled => false,
+      Status::None => self.has_window() && current_ts >= self.start_ts,
+    }
+  }
+
+  pub fn is_disabled(
+    &self,
+  ) -> bool {
+    self.status == Status::Disabled
+  }
+
+  fn is_started(
+    &self,
+  ) -> Result<bool> {
+    Ok(self.is_enabled(Clock::get()?.unix_timestamp) || self.status == Status::Disabled)
+  }
+}
+
+#[cfg(test)]
+pub mod tests {
+  use super::*;
+  use crate::state::fixtures::zeroed;
+
+  const TGE: i64 = 1_000;
+  const AMOUNT: u128 = 1_000_000_000;
+
+  pub fn epoc(tge_unlock: u64, cliff: i64, duration: i64) -> Epoc {
+    let mut epoc: Epoc = zeroed();
+    epoc.tge_unlock = tge_unlock;
+    epoc.cliff = cliff;
+    epoc.duration = duration;
+    epoc
+  }
+
+  #[test]
+  fn nothing_vests_before_tge() {
+    let epoc = epoc(200_000_000, 100, 1_000);
+    assert_eq!(epoc.get_vested_amount(AMOUNT, 0, TGE + 5_000).unwrap(), 0);
+    assert_eq!(epoc.get_vested_amount(AMOUNT, TGE, TGE - 1).unwrap(), 0);
+  }
+
+  #[test]
+  fn tge_unlock_until_cliff() {
+    let epoc = epoc(200_000_000, 100, 1_000);
+    assert_eq!(epoc.get_vested_amount(AMOUNT, TGE, TGE).unwrap(), 200_000_000);
+    assert_eq!(epoc.get_vested_amount(AMOUNT, TGE, TGE + 99).unwrap(), 200_000_000);
+    assert_eq!(epoc.get_vested_amount(AMOUNT, TGE, TGE + 100).unwrap(), 200_000_000);
+  }
+
+  #[test]
+  fn linear_after_cliff() {
+    let epoc = epoc(200_000_000, 100, 1_000);
+    assert_eq!(epoc.get_vested_amount(AMOUNT, TGE, TGE + 101).unwrap(), 200_800_000);
+    assert_eq!(epoc.get_vested_amount(AMOUNT, TGE, TGE + 600).unwrap(), 600_000_000);
+    assert_eq!(epoc.get_vested_amount(AMOUNT, TGE, TGE + 1_099).unwrap(), 999_200_000);
+  }
+
+  #[test]
+  fn fully_vested_at_end_of_duration() {
+    let epoc = epoc(200_000_000, 100, 1_000);
+    assert_eq!(epoc.get_vested_amount(AMOUNT, TGE, TGE + 1_100).unwrap(), AMOUNT);
+    assert_eq!(epoc.get_vested_amount(AMOUNT, TGE, i64::MAX).unwrap(), AMOUNT);
+  }
+
+  #[test]
+  fn without_duration_everything_vests_at_cliff() {
+    let epoc = epoc(0, 100, 0);
+    assert_eq!(epoc.get_vested_amount(AMOUNT, TGE, TGE + 99).unwrap(), 0);
+    assert_eq!(epoc.get_vested_amount(AMOUNT, TGE, TGE + 100).unwrap(), AMOUNT);
+  }
+
+  #[test]
+  fn vested_amount_rounds_down() {
+    // A third at TGE then a third of the remainder: 10 / 3 = 3 and 7 / 3 = 2
+    let epoc = epoc(333_333_333, 0, 3);
+    assert_eq!(epoc.get_vested_amount(10, TGE, TGE).unwrap(), 3);
+    assert_eq!(epoc.get_vested_amount(10, TGE, TGE + 1).unwrap(), 5);
+    assert_eq!(epoc.get_vested_amount(10, TGE, TGE + 3).unwrap(), 10);
+  }
+
+  #[test]
+  fn vested_amount_overflow() {
+    let epoc = epoc(200_000_000, 100, 1_000);
+    assert_eq!(epoc.get_vested_amount(u128::MAX, TGE, TGE).unwrap_err(), error!(errors::Store::MathOverflow));
   }
 }
\ No newline at end of file
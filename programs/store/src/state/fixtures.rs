@@ -0,0 +1,6 @@
+use anchor_lang::prelude::*;
+
+// An account with every field zeroed, tests only set the fields they exercise
+pub fn zeroed<T: AnchorDeserialize>() -> T {
+  T::deserialize(&mut &[0u8; 1024][..]).unwrap()
+}
\ No newline at end of file
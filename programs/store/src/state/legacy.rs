@@ -0,0 +1,19 @@
+use anchor_lang::prelude::*;
+
+// Promoter layout deployed before fees were escrowed on the store account
+#[derive(AnchorDeserialize)]
+pub struct LegacyPromoter {
+  pub first_fee: u64,
+  pub second_fee: u64,
+
+  pub sol_amount: u64,
+  pub usdt_amount: u64,
+  pub usdc_amount: u64,
+  pub asset_amount: u128,
+
+  pub enabled: bool,
+}
+
+impl LegacyPromoter {
+  pub const MAX_SIZE: usize = (5 * 8) + 16 + 1;
+}
\ No newline at end of file
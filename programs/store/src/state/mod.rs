@@ -1,4 +1,14 @@
 pub mod store;
 pub mod epoc;
 pub mod promoter;
-pub mod customer;
\ No newline at end of file
+pub mod customer;
+pub mod allocation;
+pub mod admin;
+pub mod payment_token;
+pub mod promoter_balance;
+pub mod contribution;
+pub mod reference_price;
+pub mod referral_code;
+pub mod legacy;
+#[cfg(test)]
+pub mod fixtures;
\ No newline at end of file
@@ -0,0 +1,172 @@
+use anchor_lang::prelude::*;
+use crate::errors;
+use crate::config::BASIS_POINTS;
+
+#[account]
+pub struct PaymentToken {
+  mint: Pubkey,
+  decimals: u8,
+  feed_id: [u8; 32],
+  price: u64,
+  peg_tolerance_bps: u64,
+  amount: u64,
+  enabled: bool,
+}
+
+impl PaymentToken {
+  pub const MAX_SIZE: usize = 32 + 1 + 32 + (3 * 8) + 1;
+
+  pub fn init(
+    &mut self,
+    mint: Pubkey,
+    decimals: u8,
+    feed_id: [u8; 32],
+    price: u64,
+    peg_tolerance_bps: u64,
+  ) -> Result<()> {
+    self.mint = mint;
+    self.decimals = decimals;
+    self.amount = 0;
+    self.enabled = true;
+
+    self.set_price(feed_id, price, peg_tolerance_bps)
+  }
+
+  pub fn set_price(
+    &mut self,
+    feed_id: [u8; 32],
+    price: u64,
+    peg_tolerance_bps: u64,
+  ) -> Result<()> {
+    if (feed_id == [0; 32] && price == 0) || peg_tolerance_bps > BASIS_POINTS {
+      return err!(errors::Store::PaymentTokenWrongPrice);
+    }
+
+    self.feed_id = feed_id;
+    self.price = price;
+    self.peg_tolerance_bps = peg_tolerance_bps;
+
+    Ok(())
+  }
+
+  pub fn set_amount(
+    &mut self,
+    amount: u64,
+  ) -> Result<()> {
+    self.amount = self.amount.checked_add(amount).ok_or(errors::Store::MathOverflow)?;
+
+    Ok(())
+  }
+
+  pub fn reset_amount(
+    &mut self,
+  ) -> Result<()> {
+    self.amount = 0;
+
+    Ok(())
+  }
+
+  pub fn enable(
+    &mut self,
+  ) -> Result<()> {
+    self.enabled = true;
+
+    Ok(())
+  }
+
+  pub fn disable(
+    &mut self,
+  ) -> Result<()> {
+    self.enabled = false;
+
+    Ok(())
+  }
+
+  pub fn get_mint(
+    &self,
+  ) -> Pubkey {
+    self.mint
+  }
+
+  pub fn get_decimals(
+    &self,
+  ) -> u8 {
+    self.decimals
+  }
+
+  pub fn get_feed_id(
+    &self,
+  ) -> [u8; 32] {
+    self.feed_id
+  }
+
+  pub fn get_price(
+    &self,
+  ) -> u64 {
+    self.price
+  }
+
+  pub fn get_amount(
+    &self,
+  ) -> u64 {
+    self.amount
+  }
+
+  pub fn is_depegged(
+    &self,
+    oracle_price: u64,
+  ) -> bool {
+    u128::from(oracle_price.abs_diff(self.price)) * u128::from(BASIS_POINTS) > u128::from(self.price) * u128::from(self.peg_tolerance_bps)
+  }
+
+  pub fn has_feed_id(
+    &self,
+  ) -> bool {
+    self.feed_id != [0; 32]
+  }
+
+  pub fn is_enabled(
+    &self,
+  ) -> bool {
+    self.enabled
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::state::fixtures::zeroed;
+
+  // Pegged at 1 USD
+  fn payment_token(peg_tolerance_bps: u64) -> PaymentToken {
+    let mut payment_token: PaymentToken = zeroed();
+    payment_token.price = 1_000_000_000;
+    payment_token.peg_tolerance_bps = peg_tolerance_bps;
+    payment_token
+  }
+
+  #[test]
+  fn within_tolerance_is_pegged() {
+    let payment_token = payment_token(50);
+    assert!(!payment_token.is_depegged(1_000_000_000));
+    assert!(!payment_token.is_depegged(1_005_000_000));
+    assert!(!payment_token.is_depegged(995_000_000));
+  }
+
+  #[test]
+  fn beyond_tolerance_is_depegged() {
+    let payment_token = payment_token(50);
+    assert!(payment_token.is_depegged(1_005_000_001));
+    assert!(payment_token.is_depegged(994_999_999));
+    assert!(payment_token.is_depegged(0));
+    assert!(payment_token.is_depegged(u64::MAX));
+  }
+
+  #[test]
+  fn without_tolerance_any_move_is_depegged() {
+    let payment_token = payment_token(0);
+    assert!(!payment_token.is_depegged(1_000_000_000));
+    assert!(payment_token.is_depegged(1_000_000_001));
+    assert!(payment_token.is_depegged(999_999_999));
+  }
+}
\ No newline at end of file
@@ -1,4 +1,6 @@
 use anchor_lang::prelude::*;
+use crate::errors;
+use crate::state::legacy::LegacyPromoter;
 
 #[account]
 pub struct Promoter {
@@ -6,15 +8,15 @@ pub struct Promoter {
   second_fee: u64,
 
   sol_amount: u64,
-  usdt_amount: u64,
-  usdc_amount: u64,
-  asset_amount: u128,
 
   enabled: bool,
+  initialized: bool,
+
+  upline: Pubkey,
 }
 
 impl Promoter {
-  pub const MAX_SIZE: usize = (5 * 8) + 16 + 1;
+  pub const MAX_SIZE: usize = (3 * 8) + 2 + 32;
 
   pub fn init(
     &mut self,
@@ -25,15 +27,29 @@ impl Promoter {
     self.second_fee = secondary_promoter_fee;
 
     self.sol_amount = 0;
-    self.usdt_amount = 0;
-    self.usdc_amount = 0;
-    self.asset_amount = 0;
 
     self.enabled = true;
+    self.initialized = true;
+
+    self.upline = Pubkey::default();
 
     Ok(())
   }
 
+  // Legacy SOL and stablecoin fees were held by the promoter itself, so they are not carried over
+  pub fn from_legacy(
+    legacy: &LegacyPromoter,
+  ) -> Promoter {
+    Promoter {
+      first_fee: legacy.first_fee,
+      second_fee: legacy.second_fee,
+      sol_amount: 0,
+      enabled: legacy.enabled,
+      initialized: true,
+      upline: Pubkey::default(),
+    }
+  }
+
   pub fn set_fee(
     &mut self,
     first_fee: u64,
@@ -45,68 +61,34 @@ impl Promoter {
     Ok(())
   }
 
-  pub fn set_sol_fee_amount(
-    &mut self,
-    fee_amount: u64,
-  ) -> Result<()> {
-    self.sol_amount += fee_amount;
-
-    Ok(())
-  }
-
-  pub fn reset_sol_fee_amount(
-    &mut self,
-  ) -> Result<()> {
-    self.sol_amount = 0;
-
-    Ok(())
-  }
-
-  pub fn set_usdt_amount(
-    &mut self,
-    usdt_amount: u64,
-  ) -> Result<()> {
-    self.usdt_amount += usdt_amount;
-
-    Ok(())
-  }
-
-  pub fn reset_usdt_amount(
-    &mut self,
-  ) -> Result<()> {
-    self.usdt_amount = 0;
-
-    Ok(())
-  }
-
-  pub fn set_usdc_amount(
+  pub fn set_upline(
     &mut self,
-    usdc_amount: u64,
+    upline: Pubkey,
   ) -> Result<()> {
-    self.usdc_amount += usdc_amount;
+    self.upline = upline;
 
     Ok(())
   }
 
-  pub fn reset_usdc_amount(
+  pub fn set_sol_fee_amount(
     &mut self,
+    fee_amount: u64,
   ) -> Result<()> {
-    self.usdc_amount = 0;
+    self.sol_amount = self.sol_amount.checked_add(fee_amount).ok_or(errors::Store::MathOverflow)?;
 
     Ok(())
   }
 
-  pub fn set_asset_amount(
+  pub fn reset_sol_fee_amount(
     &mut self,
-    asset_amount: u128,
   ) -> Result<()> {
-    self.asset_amount += asset_amount;
+    self.sol_amount = 0;
 
     Ok(())
   }
 
   pub fn get_fee(
-    &mut self,
+    &self,
   ) -> (u64, u64) {
     (self.first_fee, self.second_fee)
   }
@@ -117,22 +99,22 @@ impl Promoter {
     self.sol_amount
   }
 
-  pub fn get_usdt_amount(
-    &mut self,
-  ) -> u64 {
-    self.usdt_amount
+  pub fn get_upline(
+    &self,
+  ) -> Pubkey {
+    self.upline
   }
 
-  pub fn get_usdc_amount(
-    &mut self,
-  ) -> u64 {
-    self.usdc_amount
+  pub fn is_enabled(
+    &self,
+  ) -> bool {
+    self.enabled
   }
 
-  pub fn get_asset_amount(
-    &mut self,
-  ) -> u128 {
-    self.asset_amount
+  pub fn is_initialized(
+    &self,
+  ) -> bool {
+    self.initialized
   }
 
   pub fn enable(
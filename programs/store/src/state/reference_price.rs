@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+use crate::errors;
+
+#[account]
+pub struct ReferencePrice {
+  price: u64,
+  updated_ts: i64,
+}
+
+impl ReferencePrice {
+  pub const MAX_SIZE: usize = 2 * 8;
+
+  pub fn set_price(
+    &mut self,
+    price: u64,
+    updated_ts: i64,
+  ) -> Result<()> {
+    if price == 0 {
+      return err!(errors::Store::PriceNotPositive);
+    }
+
+    self.price = price;
+    self.updated_ts = updated_ts;
+
+    Ok(())
+  }
+
+  pub fn get_price(
+    &self,
+  ) -> u64 {
+    self.price
+  }
+
+  pub fn get_updated_ts(
+    &self,
+  ) -> i64 {
+    self.updated_ts
+  }
+
+  pub fn is_fresh(
+    &self,
+    current_ts: i64,
+    max_age: u64,
+  ) -> bool {
+    self.price > 0 && current_ts.saturating_sub(self.updated_ts) <= max_age as i64
+  }
+}
\ No newline at end of file
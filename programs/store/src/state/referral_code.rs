@@ -0,0 +1,70 @@
+use anchor_lang::prelude::*;
+use crate::errors;
+
+#[account]
+pub struct ReferralCode {
+  promoter: Pubkey,
+  revoked: bool,
+}
+
+impl ReferralCode {
+  pub const MAX_SIZE: usize = 32 + 1;
+
+  pub const MIN_LENGTH: usize = 3;
+  pub const MAX_LENGTH: usize = 16;
+
+  pub fn init(
+    &mut self,
+    code: &str,
+    promoter: Pubkey,
+  ) -> Result<()> {
+    if !Self::is_valid_code(code) {
+      return err!(errors::Store::ReferralCodeInvalid);
+    }
+
+    self.promoter = promoter;
+    self.revoked = false;
+
+    Ok(())
+  }
+
+  pub fn revoke(
+    &mut self,
+  ) -> Result<()> {
+    if self.revoked {
+      return err!(errors::Store::ReferralCodeRevoked);
+    }
+
+    self.revoked = true;
+
+    Ok(())
+  }
+
+  pub fn get_promoter(
+    &self,
+  ) -> Pubkey {
+    self.promoter
+  }
+
+  pub fn is_revoked(
+    &self,
+  ) -> bool {
+    self.revoked
+  }
+
+  // Lowercase letters, digits and dashes only, so codes cannot be spoofed with lookalikes
+  pub fn is_valid_code(
+    code: &str,
+  ) -> bool {
+    (Self::MIN_LENGTH..=Self::MAX_LENGTH).contains(&code.len())
+      && code.bytes().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == b'-')
+  }
+
+  // A referral code passed to a deposit takes the place of the raw promoter key
+  pub fn get_promoter_or(
+    referral_code: &Option<Account<ReferralCode>>,
+    promoter_key: Pubkey,
+  ) -> Pubkey {
+    referral_code.as_ref().map_or(promoter_key, |referral_code| referral_code.promoter)
+  }
+}
\ No newline at end of file
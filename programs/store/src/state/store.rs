@@ -1,6 +1,10 @@
 use anchor_lang::prelude::*;
+use pyth_solana_receiver_sdk::price_update::get_feed_id_from_hex;
 use crate::errors;
-use crate::config::{ MAX_CAP, MIN_CAP, FIRST_INTEREST, SECOND_INTEREST };
+use crate::config::{
+  MAX_CAP, MIN_CAP, FIRST_INTEREST, SECOND_INTEREST, MAX_TOTAL_INTEREST,
+  TREASURY, SOL_USD_FEED_ID, MAX_CONFIDENCE_RATIO, PRECISION, BASIS_POINTS
+};
 
 #[derive(Clone, PartialEq, AnchorDeserialize, AnchorSerialize)]
 pub enum Status {
@@ -19,10 +23,33 @@ pub struct Store {
   epoc: i16,
   status: Status,
   enabled: bool,
+  mint: Pubkey,
+  tge_ts: i64,
+  soft_cap: u64,
+  total_raised: u128,
+  sol_amount: u64,
+  treasury: Pubkey,
+  feed_id: [u8; 32],
+  wallet_max_usd: u64,
+  wallet_max_asset: u128,
+  epoc_queue: Vec<i16>,
+  max_conf_ratio: u64,
+  max_deviation_bps: u64,
+  max_price_move_bps: u64,
+  reference_max_age: u64,
+  last_price: u64,
+  last_price_ts: i64,
+  price_move_window: u64,
+  reject_invalid_promoter: bool,
+  upline_fees: Vec<u64>,
+  max_total_fee: u64,
+  legacy_epoc: i16,
 }
 
 impl Store {
-  pub const MAX_SIZE: usize = (4 * 8) + 16 + 2 + (32 + 1) * 1;
+  pub const MAX_EPOC_QUEUE: usize = 10;
+  pub const MAX_UPLINE_LEVELS: usize = 5;
+  pub const MAX_SIZE: usize = (4 * 8) + 16 + 2 + (32 + 1) * 1 + 32 + 8 + (2 * 8) + 16 + (2 * 32) + 8 + 16 + (4 + 2 * Store::MAX_EPOC_QUEUE) + 8 + (6 * 8) + 1 + (4 + 8 * Store::MAX_UPLINE_LEVELS) + 8 + 2;
 
   pub fn init(
     &mut self,
@@ -35,6 +62,42 @@ impl Store {
     self.total_sold = 0;
     self.status = Status::None;
     self.enabled = true;
+    self.mint = Pubkey::default();
+    self.tge_ts = 0;
+    self.soft_cap = 0;
+    self.total_raised = 0;
+    self.sol_amount = 0;
+    self.treasury = TREASURY.parse::<Pubkey>().unwrap();
+    self.feed_id = get_feed_id_from_hex(SOL_USD_FEED_ID)?;
+    self.wallet_max_usd = 0;
+    self.wallet_max_asset = 0;
+    self.epoc_queue = Vec::new();
+    self.max_conf_ratio = MAX_CONFIDENCE_RATIO;
+    self.max_deviation_bps = 0;
+    self.max_price_move_bps = 0;
+    self.reference_max_age = 0;
+    self.last_price = 0;
+    self.last_price_ts = 0;
+    self.price_move_window = 0;
+    self.reject_invalid_promoter = false;
+    self.upline_fees = Vec::new();
+    self.max_total_fee = MAX_TOTAL_INTEREST;
+    self.legacy_epoc = -1;
+
+    Ok(())
+  }
+
+  // Fields appended since the first deployment are zeroed by the realloc, only non-zero defaults are set
+  pub fn migrate(
+    &mut self,
+  ) -> Result<()> {
+    self.treasury = TREASURY.parse::<Pubkey>().unwrap();
+    self.feed_id = get_feed_id_from_hex(SOL_USD_FEED_ID)?;
+    self.max_conf_ratio = MAX_CONFIDENCE_RATIO;
+    self.max_total_fee = MAX_TOTAL_INTEREST;
+
+    // Purchases made before the upgrade were not tracked per epoc, they vest with the last legacy epoc
+    self.legacy_epoc = self.epoc;
 
     Ok(())
   }
@@ -54,6 +117,84 @@ impl Store {
     Ok(())
   }
 
+  pub fn set_soft_cap(
+    &mut self,
+    soft_cap: u64,
+  ) -> Result<()> {
+    if self.status == Status::Enabled || self.status == Status::Disabled {
+      return err!(errors::Store::StoreEnabled);
+    }
+
+    self.soft_cap = soft_cap;
+
+    Ok(())
+  }
+
+  pub fn set_wallet_cap(
+    &mut self,
+    wallet_max_usd: u64,
+    wallet_max_asset: u128,
+  ) -> Result<()> {
+    self.wallet_max_usd = wallet_max_usd;
+    self.wallet_max_asset = wallet_max_asset;
+
+    Ok(())
+  }
+
+  pub fn set_max_conf_ratio(
+    &mut self,
+    max_conf_ratio: u64,
+  ) -> Result<()> {
+    if max_conf_ratio > 10u64.pow(PRECISION) {
+      return err!(errors::Store::StoreWrongConfidenceRatio);
+    }
+
+    self.max_conf_ratio = max_conf_ratio;
+
+    Ok(())
+  }
+
+  pub fn set_circuit_breaker(
+    &mut self,
+    max_deviation_bps: u64,
+    max_price_move_bps: u64,
+    price_move_window: u64,
+    reference_max_age: u64,
+  ) -> Result<()> {
+    if max_deviation_bps > BASIS_POINTS || max_price_move_bps > BASIS_POINTS {
+      return err!(errors::Store::StoreWrongCircuitBreaker);
+    }
+
+    self.max_deviation_bps = max_deviation_bps;
+    self.max_price_move_bps = max_price_move_bps;
+    self.price_move_window = price_move_window;
+    self.reference_max_age = reference_max_age;
+    self.last_price = 0;
+    self.last_price_ts = 0;
+
+    Ok(())
+  }
+
+  pub fn set_last_price(
+    &mut self,
+    last_price: u64,
+    last_price_ts: i64,
+  ) -> Result<()> {
+    self.last_price = last_price;
+    self.last_price_ts = last_price_ts;
+
+    Ok(())
+  }
+
+  pub fn set_promoter_policy(
+    &mut self,
+    reject_invalid_promoter: bool,
+  ) -> Result<()> {
+    self.reject_invalid_promoter = reject_invalid_promoter;
+
+    Ok(())
+  }
+
   pub fn set_fee(
     &mut self,
     first_fee: u64,
@@ -73,6 +214,25 @@ impl Store {
     Ok(())
   }
 
+  pub fn set_upline_fees(
+    &mut self,
+    upline_fees: Vec<u64>,
+    max_total_fee: u64,
+  ) -> Result<()> {
+    if upline_fees.len() > Store::MAX_UPLINE_LEVELS {
+      return err!(errors::Store::StoreUplineLevelsExceeded);
+    }
+
+    if max_total_fee > 10u64.pow(PRECISION) || upline_fees.iter().any(|fee| *fee > max_total_fee) {
+      return err!(errors::Store::StoreMainPromoterRewardTooLarge);
+    }
+
+    self.upline_fees = upline_fees;
+    self.max_total_fee = max_total_fee;
+
+    Ok(())
+  }
+
   pub fn set_enable(
     &mut self,
   ) -> Result<()> {
@@ -106,11 +266,112 @@ impl Store {
     Ok(())
   }
 
+  pub fn set_epoc_queue(
+    &mut self,
+    epoc_queue: Vec<i16>,
+  ) -> Result<()> {
+    if epoc_queue.len() > Store::MAX_EPOC_QUEUE {
+      return err!(errors::Store::StoreEpocQueueFull);
+    }
+
+    if epoc_queue.iter().enumerate().any(|(i, id)| *id < 0 || *id == self.epoc || epoc_queue[..i].contains(id)) {
+      return err!(errors::Store::StoreWrongEpocQueue);
+    }
+
+    self.epoc_queue = epoc_queue;
+
+    Ok(())
+  }
+
+  pub fn advance_epoc(
+    &mut self,
+  ) -> Result<i16> {
+    if self.epoc_queue.is_empty() {
+      return err!(errors::Store::EpocSupplyExceeded);
+    }
+
+    self.epoc = self.epoc_queue.remove(0);
+
+    Ok(self.epoc)
+  }
+
   pub fn set_total_sold(
     &mut self,
     total_sold: u128,
   ) -> Result<()> {
-    self.total_sold += total_sold;
+    self.total_sold = self.total_sold.checked_add(total_sold).ok_or(errors::Store::MathOverflow)?;
+
+    Ok(())
+  }
+
+  pub fn set_total_raised(
+    &mut self,
+    total_raised: u128,
+  ) -> Result<()> {
+    self.total_raised = self.total_raised.checked_add(total_raised).ok_or(errors::Store::MathOverflow)?;
+
+    Ok(())
+  }
+
+  pub fn set_sol_amount(
+    &mut self,
+    sol_amount: u64,
+  ) -> Result<()> {
+    self.sol_amount = self.sol_amount.checked_add(sol_amount).ok_or(errors::Store::MathOverflow)?;
+
+    Ok(())
+  }
+
+  pub fn reset_sol_amount(
+    &mut self,
+  ) -> Result<()> {
+    self.sol_amount = 0;
+
+    Ok(())
+  }
+
+  pub fn set_mint(
+    &mut self,
+    mint: Pubkey,
+  ) -> Result<()> {
+    self.mint = mint;
+
+    Ok(())
+  }
+
+  pub fn set_treasury(
+    &mut self,
+    treasury: Pubkey,
+  ) -> Result<()> {
+    self.treasury = treasury;
+
+    Ok(())
+  }
+
+  pub fn set_feed_id(
+    &mut self,
+    feed_id: [u8; 32],
+  ) -> Result<()> {
+    self.feed_id = feed_id;
+
+    Ok(())
+  }
+
+  pub fn set_tge(
+    &mut self,
+    tge_ts: i64,
+    current_ts: i64,
+  ) -> Result<()> {
+    // Every vesting schedule runs from the TGE, so it cannot move once set nor be set in the past
+    if self.tge_ts != 0 {
+      return err!(errors::Store::StoreTgeLocked);
+    }
+
+    if tge_ts < current_ts {
+      return err!(errors::Store::StoreWrongTge);
+    }
+
+    self.tge_ts = tge_ts;
 
     Ok(())
   }
@@ -139,15 +400,214 @@ impl Store {
     self.total_sold
   }
 
+  pub fn get_total_raised(
+    &self,
+  ) -> u128 {
+    self.total_raised
+  }
+
+  pub fn get_sol_amount(
+    &self,
+  ) -> u64 {
+    self.sol_amount
+  }
+
   pub fn get_fee(
-    &mut self,
+    &self,
   ) -> (u64, u64) {
     (self.first_fee, self.second_fee)
   }
 
+  pub fn get_mint(
+    &self,
+  ) -> Pubkey {
+    self.mint
+  }
+
+  pub fn get_treasury(
+    &self,
+  ) -> Pubkey {
+    self.treasury
+  }
+
+  pub fn get_feed_id(
+    &self,
+  ) -> [u8; 32] {
+    self.feed_id
+  }
+
+  pub fn get_tge(
+    &self,
+  ) -> i64 {
+    self.tge_ts
+  }
+
+  pub fn get_max_conf_ratio(
+    &self,
+  ) -> u64 {
+    self.max_conf_ratio
+  }
+
+  pub fn get_reference_max_age(
+    &self,
+  ) -> u64 {
+    self.reference_max_age
+  }
+
+  pub fn has_deviation_check(
+    &self,
+  ) -> bool {
+    self.max_deviation_bps > 0
+  }
+
+  pub fn is_deviation_exceeded(
+    &self,
+    price: u64,
+    reference_price: u64,
+  ) -> bool {
+    self.max_deviation_bps > 0
+      && u128::from(price.abs_diff(reference_price)) * u128::from(BASIS_POINTS) > u128::from(reference_price) * u128::from(self.max_deviation_bps)
+  }
+
+  // Only a price accepted within the window is compared, so a quiet market cannot lock deposits out
+  pub fn is_price_move_exceeded(
+    &self,
+    price: u64,
+    current_ts: i64,
+  ) -> bool {
+    self.max_price_move_bps > 0
+      && self.last_price > 0
+      && current_ts.saturating_sub(self.last_price_ts) <= self.price_move_window as i64
+      && u128::from(price.abs_diff(self.last_price)) * u128::from(BASIS_POINTS) > u128::from(self.last_price) * u128::from(self.max_price_move_bps)
+  }
+
+  pub fn is_invalid_promoter_rejected(
+    &self,
+  ) -> bool {
+    self.reject_invalid_promoter
+  }
+
+  pub fn get_upline_fees(
+    &self,
+  ) -> Vec<u64> {
+    self.upline_fees.clone()
+  }
+
+  pub fn get_max_total_fee(
+    &self,
+  ) -> u64 {
+    self.max_total_fee
+  }
+
+  pub fn get_legacy_epoc(
+    &self,
+  ) -> i16 {
+    self.legacy_epoc
+  }
+
+  pub fn get_next_epoc(
+    &self,
+  ) -> i16 {
+    *self.epoc_queue.first().unwrap_or(&-1)
+  }
+
+  pub fn is_wallet_cap_exceeded(
+    &self,
+    usd_amount: u128,
+    asset_amount: u128,
+  ) -> bool {
+    (self.wallet_max_usd > 0 && usd_amount > u128::from(self.wallet_max_usd))
+      || (self.wallet_max_asset > 0 && asset_amount > self.wallet_max_asset)
+  }
+
   pub fn is_enabled(
     &self,
   ) -> bool {
     self.status == Status::Enabled
   }
+
+  pub fn is_disabled(
+    &self,
+  ) -> bool {
+    self.status == Status::Disabled
+  }
+
+  pub fn is_soft_cap_reached(
+    &self,
+  ) -> bool {
+    self.total_raised >= u128::from(self.soft_cap)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::state::fixtures::zeroed;
+
+  #[test]
+  fn tge_is_locked_once_set() {
+    let mut store: Store = zeroed();
+    store.set_tge(2_000, 1_000).unwrap();
+    assert_eq!(store.get_tge(), 2_000);
+    assert_eq!(store.set_tge(3_000, 1_000).unwrap_err(), error!(errors::Store::StoreTgeLocked));
+  }
+
+  #[test]
+  fn tge_cannot_be_in_the_past() {
+    let mut store: Store = zeroed();
+    assert_eq!(store.set_tge(999, 1_000).unwrap_err(), error!(errors::Store::StoreWrongTge));
+    store.set_tge(1_000, 1_000).unwrap();
+  }
+
+  #[test]
+  fn legacy_epoc_is_the_epoc_at_upgrade() {
+    let mut store: Store = zeroed();
+    store.epoc = 2;
+    store.migrate().unwrap();
+    assert_eq!(store.get_legacy_epoc(), 2);
+
+    store.init().unwrap();
+    assert_eq!(store.get_legacy_epoc(), -1);
+  }
+
+  #[test]
+  fn deviation_check_disabled() {
+    let store: Store = zeroed();
+    assert!(!store.has_deviation_check());
+    assert!(!store.is_deviation_exceeded(u64::MAX, 1));
+    assert!(!store.is_deviation_exceeded(0, 1_000_000_000));
+  }
+
+  #[test]
+  fn deviation_bounds() {
+    let mut store: Store = zeroed();
+    store.max_deviation_bps = 100;
+    assert!(!store.is_deviation_exceeded(150_000_000_000, 150_000_000_000));
+    assert!(!store.is_deviation_exceeded(151_500_000_000, 150_000_000_000));
+    assert!(!store.is_deviation_exceeded(148_500_000_000, 150_000_000_000));
+    assert!(store.is_deviation_exceeded(151_500_000_001, 150_000_000_000));
+    assert!(store.is_deviation_exceeded(148_499_999_999, 150_000_000_000));
+  }
+
+  #[test]
+  fn deviation_does_not_overflow() {
+    let mut store: Store = zeroed();
+    store.max_deviation_bps = BASIS_POINTS;
+    assert!(store.is_deviation_exceeded(u64::MAX, 1));
+    assert!(!store.is_deviation_exceeded(0, u64::MAX));
+  }
+
+  #[test]
+  fn price_move_only_within_window() {
+    let mut store: Store = zeroed();
+    store.max_price_move_bps = 500;
+    store.price_move_window = 60;
+    assert!(!store.is_price_move_exceeded(u64::MAX, 1_000));
+
+    store.set_last_price(100_000_000_000, 1_000).unwrap();
+    assert!(!store.is_price_move_exceeded(105_000_000_000, 1_060));
+    assert!(store.is_price_move_exceeded(105_000_000_001, 1_060));
+    assert!(store.is_price_move_exceeded(94_999_999_999, 1_060));
+    assert!(!store.is_price_move_exceeded(200_000_000_000, 1_061));
+  }
 }